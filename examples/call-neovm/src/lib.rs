@@ -70,7 +70,7 @@ pub fn invoke() {
             let amount: U128 = source.read().unwrap();
 
             let data =
-                neo::call_contract(&NEO_CONTRACT_ADDR, ("transfer", from_addr, to_addr, amount));
+                neo::call_contract(&NEO_CONTRACT_ADDR, ("transfer", (from_addr, to_addr, amount)));
             let mut parser = VmValueParser::new(&data);
             let boo = parser.bool().unwrap_or(false);
             sink.write(boo);
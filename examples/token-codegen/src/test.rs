@@ -1,4 +1,5 @@
-use crate::{MyToken, MyTokenInstance};
+use crate::{invoke, MyToken, MyTokenInstance};
+use ontio_std::abi::{Sink, Source};
 use ontio_std::mock::build_runtime;
 use ontio_std::types::{Address, U128};
 
@@ -25,12 +26,14 @@ fn transfer_no_witness() {
 fn transfer() {
     let owner = &Address::random();
     let b = &Address::random();
-    build_runtime().witness(&[owner]);
+    let handle = build_runtime();
+    handle.witness(&[owner]);
     let mut token = MyTokenInstance;
     assert!(token.initialize(owner));
 
     assert_eq!(token.transfer(owner, b, U128::new(123)), true);
     assert_eq!(token.balance_of(b), U128::new(123));
+    handle.assert_emitted("Transfer", &[owner.into(), b.into(), U128::new(123).into()]);
 
     let total = token.total_supply();
     assert_eq!(token.balance_of(owner), total - U128::new(123));
@@ -66,3 +69,31 @@ fn transfer_multi() {
     assert_eq!(token.balance_of(&alice), U128::new(1));
     assert_eq!(token.balance_of(&bob), U128::new(2));
 }
+
+#[test]
+fn invoke_dispatch() {
+    let owner = &Address::random();
+    let b = &Address::random();
+    let handle = build_runtime();
+    handle.witness(&[owner]);
+
+    let mut sink = Sink::new(16);
+    sink.write(("initialize", owner));
+    assert_eq!(handle.invoke(invoke, sink.bytes()), Ok(vec![1]));
+
+    let mut sink = Sink::new(16);
+    sink.write(("transfer", owner, b, U128::new(123)));
+    assert_eq!(handle.invoke(invoke, sink.bytes()), Ok(vec![1]));
+
+    let mut sink = Sink::new(16);
+    sink.write(("balance_of", b));
+    let output = handle.invoke(invoke, sink.bytes()).unwrap();
+    assert_eq!(Source::new(&output).read::<U128>().unwrap(), U128::new(123));
+
+    let mut sink = Sink::new(16);
+    sink.write("burn");
+    assert_eq!(
+        handle.invoke(invoke, sink.bytes()),
+        Err(r#"{"code":2,"message":"invoke unsupported method"}"#.to_string())
+    );
+}
@@ -1,3 +1,4 @@
+use heck::ShoutySnakeCase;
 use proc_macro2::Span;
 use quote::quote;
 use quote::ToTokens;
@@ -7,11 +8,15 @@ pub fn quote(item: syn::Item) -> proc_macro2::TokenStream {
         syn::Item::Trait(item_trait) => {
             let contract = Contract::from_item_trait(item_trait);
             let disp = generate_dispatcher(&contract);
+            let client = generate_client(&contract);
+            let abi = generate_abi(&contract);
             let trait_and_event = generate_event(&contract);
             quote! {
                 extern crate alloc;
                 #trait_and_event
                 #disp
+                #client
+                #abi
             }
         }
         _ => {
@@ -128,6 +133,9 @@ impl ContractEvent {
 }
 
 fn generate_dispatcher(contract: &Contract) -> proc_macro2::TokenStream {
+    let decode_err = quote! {
+        unwrap_or_else(|_| ontio_std::abi::ContractError::revert(&ontio_std::abi::StdError::BadArgument))
+    };
     let actions: Vec<proc_macro2::TokenStream> = contract.fields.iter().filter_map(|field| {
         match field {
             ContractField::Action(ref action) => {
@@ -143,26 +151,40 @@ fn generate_dispatcher(contract: &Contract) -> proc_macro2::TokenStream {
                                 syn::Type::Slice(slice) => {
                                     let slice_elem = &slice.elem;
                                     match mutability {
-                                        Some(_) => quote! { source.read::<alloc::vec::Vec<#slice_elem>>().expect(arg_decode_err).as_mut_slice() },
-                                        None => quote! { source.read::<alloc::vec::Vec<#slice_elem>>().expect(arg_decode_err).as_slice() },
+                                        Some(_) => quote! { source.read::<alloc::vec::Vec<#slice_elem>>().#decode_err.as_mut_slice() },
+                                        None => quote! { source.read::<alloc::vec::Vec<#slice_elem>>().#decode_err.as_slice() },
                                     }
                                 }
                                 syn::Type::Path(ref path) => {
                                     if path.clone().into_token_stream().to_string() == "str" {
-                                        quote! { source.read::<String>().expect(arg_decode_err).as_str() }
+                                        quote! { source.read::<String>().#decode_err.as_str() }
                                     } else {
-                                        quote! { &#mutability source.read::<#real>().expect(arg_decode_err) }
+                                        quote! { &#mutability source.read::<#real>().#decode_err }
                                     }
                                 }
-                                real => quote! { &#mutability source.read::<#real>().expect(arg_decode_err) },
+                                real => quote! { &#mutability source.read::<#real>().#decode_err },
                             }
                         }
                         ty => {
-                            quote! { source.read::<#ty>().expect(arg_decode_err) }
+                            quote! { source.read::<#ty>().#decode_err }
                         }
                     }
                 });
                 match action.ret {
+                    Some(ref ret) if result_ok_type(ret).is_some() => {
+                        Some(quote!{
+                            #action_literal => {
+                                match contract_instance.#action_name(#(#args),*) {
+                                    Ok(res) => {
+                                        let mut sink = ontio_std::abi::Sink::new(16);
+                                        sink.write(res);
+                                        sink.into()
+                                    }
+                                    Err(err) => ontio_std::abi::ContractError::revert(&err),
+                                }
+                            }
+                        })
+                    }
                     Some(_) => {
                         Some(quote!{
                             #action_literal => {
@@ -176,7 +198,7 @@ fn generate_dispatcher(contract: &Contract) -> proc_macro2::TokenStream {
                     None => {
                         Some(quote!{
                             #action_literal => {
-                                contract_instance.#action_name(#(source.read::<#args>().expect(arg_decode_err)),*);
+                                contract_instance.#action_name(#(#args),*);
                                 alloc::vec::Vec::new()
                             }
                         })
@@ -210,17 +232,177 @@ fn generate_dispatcher(contract: &Contract) -> proc_macro2::TokenStream {
                 let contract_instance = &mut self.contract_instance;
                 // todo: avoid bytes copy
                 let mut source = ontio_std::abi::Source::new(payload);
-                let action = source.read::<String>().unwrap();
-                let arg_decode_err = "failed to decode argument";
+                let action = source.read::<String>().#decode_err;
                 match action.as_str() {
                     #(#actions,)*
-                    _ => panic!("invoke unsupported method"),
+                    _ => ontio_std::abi::ContractError::revert(&ontio_std::abi::StdError::UnknownMethod),
                 }
             }
         }
     }
 }
 
+fn result_ok_type(ty: &syn::Type) -> Option<&syn::Type> {
+    match ty {
+        syn::Type::Path(path) => {
+            let seg = path.path.segments.last()?;
+            if seg.ident != "Result" {
+                return None;
+            }
+            match seg.arguments {
+                syn::PathArguments::AngleBracketed(ref args) => {
+                    args.args.iter().find_map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn generate_client(contract: &Contract) -> proc_macro2::TokenStream {
+    let decode_err = quote! {
+        unwrap_or_else(|_| ontio_std::abi::ContractError::revert(&ontio_std::abi::StdError::BadReturnValue))
+    };
+    let methods: Vec<proc_macro2::TokenStream> = contract
+        .fields
+        .iter()
+        .filter_map(|field| match field {
+            ContractField::Action(ref action) => Some(action),
+            _ => None,
+        })
+        .map(|action| {
+            let action_name = &action.name;
+            let action_literal = syn::LitStr::new(&action_name.to_string(), Span::call_site());
+            let params = action.params.iter().map(|(pat, ty)| quote! { #pat: #ty });
+            let writes = action.params.iter().map(|(pat, ty)| match **ty {
+                syn::Type::Reference(ref refer) if refer.mutability.is_some() => {
+                    quote! { sink.write(&*#pat); }
+                }
+                _ => quote! { sink.write(#pat); },
+            });
+            let encode = quote! {
+                let mut sink = ontio_std::abi::Sink::new(16);
+                sink.write(#action_literal);
+                #(#writes)*
+            };
+            //a failed call aborts the caller on chain, so the client only returns the `Ok` value.
+            match action.ret.as_ref().map(|ret| result_ok_type(ret).unwrap_or(ret)) {
+                Some(ret) => quote! {
+                    pub fn #action_name(&self, #(#params),*) -> #ret {
+                        #encode
+                        let output = ontio_std::runtime::call_contract(&self.address, sink.bytes());
+                        let mut source = ontio_std::abi::Source::new(&output);
+                        source.read::<#ret>().#decode_err
+                    }
+                },
+                None => quote! {
+                    pub fn #action_name(&self, #(#params),*) {
+                        #encode
+                        ontio_std::runtime::call_contract(&self.address, sink.bytes());
+                    }
+                },
+            }
+        })
+        .collect();
+
+    let contract_name = &contract.name;
+    let client_name = syn::Ident::new(&format!("{contract_name}Client"), Span::call_site());
+
+    quote! {
+        pub struct #client_name {
+            pub address: ontio_std::types::Address,
+        }
+
+        impl #client_name {
+            pub fn new(address: ontio_std::types::Address) -> Self {
+                Self { address }
+            }
+
+            #(#methods)*
+        }
+    }
+}
+
+fn abi_type_name(ty: &syn::Type) -> String {
+    match ty {
+        syn::Type::Reference(refer) => abi_type_name(&refer.elem),
+        syn::Type::Paren(paren) => abi_type_name(&paren.elem),
+        syn::Type::Slice(slice) => format!("[{}]", abi_type_name(&slice.elem)),
+        ty => ty.into_token_stream().to_string().replace(' ', ""),
+    }
+}
+
+fn abi_params(params: &[(Box<syn::Pat>, Box<syn::Type>)]) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .map(|(pat, ty)| {
+            let name = match **pat {
+                syn::Pat::Ident(ref ident) => ident.ident.to_string(),
+                ref pat => pat.into_token_stream().to_string(),
+            };
+            format!(r#"{{"name":"{}","type":"{}"}}"#, name, abi_type_name(ty))
+        })
+        .collect();
+    format!("[{}]", params.join(","))
+}
+
+/// Describe the contract interface as json, the layout is:
+/// `{"contract":<name>,"actions":[{"name":<name>,"parameters":[{"name":<name>,"type":<type>}],"return":<type>}],
+/// "events":[{"name":<name>,"parameters":[{"name":<name>,"type":<type>}]}]}`.
+/// References are removed from the types since they are encoded as the referenced value, `()` is used for
+/// actions without return value and the `Ok` type is used for actions returning `Result`.
+fn abi_json(contract: &Contract) -> String {
+    let mut actions = Vec::new();
+    let mut events = Vec::new();
+    for field in contract.fields.iter() {
+        match field {
+            ContractField::Action(action) => {
+                let ret = match action.ret {
+                    Some(ref ret) => abi_type_name(result_ok_type(ret).unwrap_or(ret)),
+                    None => "()".to_string(),
+                };
+                actions.push(format!(
+                    r#"{{"name":"{}","parameters":{},"return":"{}"}}"#,
+                    action.name,
+                    abi_params(&action.params),
+                    ret
+                ));
+            }
+            ContractField::Event(event) => {
+                events.push(format!(
+                    r#"{{"name":"{}","parameters":{}}}"#,
+                    event.method_sig.ident,
+                    abi_params(&event.params)
+                ));
+            }
+            ContractField::Unhandle(_) => {}
+        }
+    }
+
+    format!(
+        r#"{{"contract":"{}","actions":[{}],"events":[{}]}}"#,
+        contract.name,
+        actions.join(","),
+        events.join(",")
+    )
+}
+
+fn generate_abi(contract: &Contract) -> proc_macro2::TokenStream {
+    let abi_name = syn::Ident::new(
+        &format!("{}_ABI", contract.name.to_string().to_shouty_snake_case()),
+        Span::call_site(),
+    );
+    let abi = syn::LitStr::new(&abi_json(contract), Span::call_site());
+    quote! {
+        #[allow(dead_code)]
+        pub const #abi_name: &str = #abi;
+    }
+}
+
 fn generate_event(contract: &Contract) -> proc_macro2::TokenStream {
     let events: Vec<proc_macro2::TokenStream> = contract
         .fields
@@ -0,0 +1,106 @@
+use syn::{Attribute, Lit, Meta, NestedMeta};
+
+///Items of the `#[codec(...)]` attributes.
+pub fn codec_metas(attrs: &[Attribute]) -> syn::Result<Vec<NestedMeta>> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("codec")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => metas.extend(list.nested),
+            meta => return Err(syn::Error::new_spanned(meta, "expected `#[codec(...)]`")),
+        }
+    }
+    Ok(metas)
+}
+
+///How the variant of an enum is encoded.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tag {
+    ///the name of the variant as a string, the default.
+    Name,
+    ///the index of the variant as a varuint.
+    Index,
+}
+
+pub struct ContainerAttrs {
+    pub tag: Tag,
+}
+
+impl ContainerAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container = ContainerAttrs { tag: Tag::Name };
+        for meta in codec_metas(attrs)? {
+            match meta {
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("tag") => {
+                    container.tag = match nv.lit {
+                        Lit::Str(ref tag) if tag.value() == "name" => Tag::Name,
+                        Lit::Str(ref tag) if tag.value() == "index" => Tag::Index,
+                        ref lit => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "expected \"name\" or \"index\"",
+                            ))
+                        }
+                    }
+                }
+                meta => return Err(syn::Error::new_spanned(meta, "unknown codec attribute")),
+            }
+        }
+        Ok(container)
+    }
+}
+
+#[derive(Default)]
+pub struct VariantAttrs {
+    ///explicit index of the variant with the `index` tag.
+    pub index: Option<u64>,
+}
+
+impl VariantAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut variant = VariantAttrs::default();
+        for meta in codec_metas(attrs)? {
+            match meta {
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("index") => {
+                    variant.index = match nv.lit {
+                        Lit::Int(ref index) => Some(index.base10_parse()?),
+                        ref lit => return Err(syn::Error::new_spanned(lit, "expected an integer")),
+                    }
+                }
+                meta => {
+                    return Err(syn::Error::new_spanned(meta, "unknown codec variant attribute"))
+                }
+            }
+        }
+        Ok(variant)
+    }
+}
+
+#[derive(Default)]
+pub struct FieldAttrs {
+    ///not encoded, decoded as the default value.
+    pub skip: bool,
+    ///decoded as the default value when the data ends before the field.
+    pub default: bool,
+}
+
+impl FieldAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut field = FieldAttrs::default();
+        for meta in codec_metas(attrs)? {
+            match meta {
+                NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("skip") => {
+                    field.skip = true
+                }
+                NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("default") => {
+                    field.default = true
+                }
+                meta => return Err(syn::Error::new_spanned(meta, "unknown codec field attribute")),
+            }
+        }
+        if field.skip && field.default {
+            let attr = attrs.iter().find(|attr| attr.path.is_ident("codec"));
+            return Err(syn::Error::new_spanned(attr, "`skip` and `default` are exclusive"));
+        }
+        Ok(field)
+    }
+}
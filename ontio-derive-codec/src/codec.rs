@@ -0,0 +1,296 @@
+use crate::attr::{ContainerAttrs, FieldAttrs, Tag, VariantAttrs};
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse_quote, Data, DeriveInput, Fields, GenericParam, Generics, Ident, Index, LitStr, Member,
+    Type,
+};
+
+pub struct Field<'a> {
+    pub member: Member,
+    pub binding: Ident,
+    pub ty: &'a Type,
+    pub attrs: FieldAttrs,
+}
+
+pub fn parse_fields(fields: &Fields) -> syn::Result<Vec<Field<'_>>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let member = match field.ident {
+                Some(ref ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index::from(i)),
+            };
+            let attrs = FieldAttrs::parse(&field.attrs)?;
+            Ok(Field { member, binding: format_ident!("__field{}", i), ty: &field.ty, attrs })
+        })
+        .collect()
+}
+
+///A struct, or a variant of an enum with its tag.
+pub struct Shape<'a> {
+    pub path: TokenStream,
+    pub tag: TokenStream,
+    pub fields: Vec<Field<'a>>,
+}
+
+fn parse_shapes(ast: &DeriveInput) -> syn::Result<(Tag, Vec<Shape<'_>>)> {
+    let name = &ast.ident;
+    let container = ContainerAttrs::parse(&ast.attrs)?;
+    match ast.data {
+        Data::Struct(ref data) => {
+            if container.tag == Tag::Index {
+                return Err(syn::Error::new_spanned(name, "`tag` is only supported by enums"));
+            }
+            let fields = parse_fields(&data.fields)?;
+            Ok((container.tag, vec![Shape { path: quote!(#name), tag: quote!(), fields }]))
+        }
+        Data::Enum(ref data) => {
+            let mut shapes = Vec::new();
+            let mut indexes: Vec<u64> = Vec::new();
+            for variant in data.variants.iter() {
+                let ident = &variant.ident;
+                let attrs = VariantAttrs::parse(&variant.attrs)?;
+                let tag = match container.tag {
+                    Tag::Name => {
+                        if attrs.index.is_some() {
+                            let msg = "`index` requires `#[codec(tag = \"index\")]`";
+                            return Err(syn::Error::new_spanned(ident, msg));
+                        }
+                        let name = LitStr::new(&ident.to_string(), ident.span());
+                        quote!(#name)
+                    }
+                    Tag::Index => {
+                        //as the discriminants, one more than the previous variant by default.
+                        let index = match (attrs.index, indexes.last()) {
+                            (Some(index), _) => index,
+                            (None, Some(&last)) => last + 1,
+                            (None, None) => 0,
+                        };
+                        if indexes.contains(&index) {
+                            let msg = format!("duplicate variant index {}", index);
+                            return Err(syn::Error::new_spanned(ident, msg));
+                        }
+                        indexes.push(index);
+                        quote!(#index)
+                    }
+                };
+                let fields = parse_fields(&variant.fields)?;
+                shapes.push(Shape { path: quote!(#name::#ident), tag, fields });
+            }
+            Ok((container.tag, shapes))
+        }
+        Data::Union(ref data) => {
+            Err(syn::Error::new_spanned(data.union_token, "union is not supported"))
+        }
+    }
+}
+
+impl<'a> Shape<'a> {
+    ///Pattern binding the encoded fields of `self`.
+    pub fn pattern(&self) -> TokenStream {
+        let path = &self.path;
+        let bindings = self.fields.iter().map(|field| {
+            let member = &field.member;
+            if field.attrs.skip {
+                quote!(#member: _)
+            } else {
+                let binding = &field.binding;
+                quote!(#member: #binding)
+            }
+        });
+        quote!(#path { #(#bindings),* })
+    }
+
+    fn encode(&self) -> TokenStream {
+        let writes = self.fields.iter().filter(|field| !field.attrs.skip).map(|field| {
+            let binding = &field.binding;
+            quote!(sink.write(#binding);)
+        });
+        quote!(#(#writes)*)
+    }
+
+    fn decode(&self) -> TokenStream {
+        let path = &self.path;
+        let values = self.fields.iter().map(|field| {
+            let member = &field.member;
+            if field.attrs.skip {
+                quote!(#member: ::core::default::Default::default())
+            } else if field.attrs.default {
+                quote!(#member: if source.remaining() == 0 {
+                    ::core::default::Default::default()
+                } else {
+                    source.read()?
+                })
+            } else {
+                quote!(#member: source.read()?)
+            }
+        });
+        quote!(#path { #(#values),* })
+    }
+}
+
+///Type parameters of `generics` occurring in `ty`.
+fn type_params_in(generics: &Generics, ty: &Type) -> Vec<Ident> {
+    fn collect(tokens: TokenStream, idents: &mut Vec<Ident>) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => idents.push(ident),
+                TokenTree::Group(group) => collect(group.stream(), idents),
+                _ => {}
+            }
+        }
+    }
+    let mut idents = Vec::new();
+    collect(ty.to_token_stream(), &mut idents);
+    generics.type_params().map(|param| param.ident.clone()).filter(|p| idents.contains(p)).collect()
+}
+
+///Add `bound` to the type parameters used by the fields selected by `filter`, and `Default` to
+///the types of the fields decoded as their default value.
+pub fn add_bounds(
+    generics: &mut Generics, shapes: &[Shape<'_>], bound: TokenStream,
+    filter: fn(&FieldAttrs) -> bool, default: fn(&FieldAttrs) -> bool,
+) {
+    let mut params: Vec<Ident> = Vec::new();
+    let mut defaults: Vec<&Type> = Vec::new();
+    for field in shapes.iter().flat_map(|shape| shape.fields.iter()) {
+        let used = type_params_in(generics, field.ty);
+        if used.is_empty() {
+            continue;
+        }
+        if filter(&field.attrs) {
+            params.extend(used);
+        }
+        if default(&field.attrs) {
+            defaults.push(field.ty);
+        }
+    }
+    let where_clause = generics.make_where_clause();
+    for (i, param) in params.iter().enumerate() {
+        if !params[..i].contains(param) {
+            where_clause.predicates.push(parse_quote!(#param: #bound));
+        }
+    }
+    for ty in defaults {
+        where_clause.predicates.push(parse_quote!(#ty: ::core::default::Default));
+    }
+}
+
+///Lifetime of the decoded data and the generics of a decoder impl.
+pub fn decoder_generics(generics: &Generics) -> (syn::Lifetime, Generics) {
+    let lifetime = syn::Lifetime::new("'__codec", Span::call_site());
+    let mut generics = generics.clone();
+    //the data outlives the borrowed fields.
+    let bounds: Vec<_> = generics.lifetimes().map(|param| param.lifetime.clone()).collect();
+    let param = if bounds.is_empty() {
+        parse_quote!(#lifetime)
+    } else {
+        parse_quote!(#lifetime: #(#bounds)+*)
+    };
+    generics.params.insert(0, GenericParam::Lifetime(param));
+    (lifetime, generics)
+}
+
+pub fn quote_encoder(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &ast.ident;
+    let (tag, shapes) = parse_shapes(ast)?;
+    let mut generics = ast.generics.clone();
+    add_bounds(&mut generics, &shapes, quote!(ontio_std::abi::Encoder), |f| !f.skip, |_| false);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match ast.data {
+        Data::Struct(_) => {
+            let pattern = shapes[0].pattern();
+            let encode = shapes[0].encode();
+            quote! {
+                let #pattern = self;
+                #encode
+            }
+        }
+        _ => {
+            let arms = shapes.iter().map(|shape| {
+                let pattern = shape.pattern();
+                let variant = &shape.tag;
+                let write_tag = match tag {
+                    Tag::Name => quote!(sink.write(#variant);),
+                    Tag::Index => quote!(sink.write_varuint(#variant);),
+                };
+                let encode = shape.encode();
+                quote! {
+                    #pattern => {
+                        #write_tag
+                        #encode
+                    }
+                }
+            });
+            //an empty enum has no value to encode.
+            let value = if shapes.is_empty() { quote!(*self) } else { quote!(self) };
+            quote! {
+                match #value {
+                    #(#arms)*
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ontio_std::abi::Encoder for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn encode(&self, sink: &mut ontio_std::abi::Sink) {
+                #body
+            }
+        }
+    })
+}
+
+pub fn quote_decoder(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &ast.ident;
+    let (tag, shapes) = parse_shapes(ast)?;
+    let (lifetime, mut generics) = decoder_generics(&ast.generics);
+    add_bounds(
+        &mut generics,
+        &shapes,
+        quote!(ontio_std::abi::Decoder<#lifetime>),
+        |f| !f.skip,
+        |f| f.skip || f.default,
+    );
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+    let body = match ast.data {
+        Data::Struct(_) => {
+            let decode = shapes[0].decode();
+            quote!(::core::result::Result::Ok(#decode))
+        }
+        _ => {
+            let arms = shapes.iter().map(|shape| {
+                let variant = &shape.tag;
+                let decode = shape.decode();
+                quote!(#variant => ::core::result::Result::Ok(#decode),)
+            });
+            let read_tag = match tag {
+                Tag::Name => quote!(source.read::<&str>()?),
+                Tag::Index => quote!(source.read_varuint()?),
+            };
+            quote! {
+                match #read_tag {
+                    #(#arms)*
+                    _ => ::core::result::Result::Err(ontio_std::abi::Error::IrregularData),
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ontio_std::abi::Decoder<#lifetime> for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn decode(
+                source: &mut ontio_std::abi::Source<#lifetime>,
+            ) -> ::core::result::Result<Self, ontio_std::abi::Error> {
+                #body
+            }
+        }
+    })
+}
@@ -2,145 +2,46 @@
 #![feature(proc_macro_hygiene)]
 
 extern crate proc_macro;
-use heck::MixedCase;
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{Data, DataEnum, DataStruct, Fields};
 
-#[proc_macro_derive(Encoder)]
+mod attr;
+mod codec;
+mod vm_value;
+
+///Derive `Encoder` for structs and enums.
+///
+///The fields are encoded in order, an enum first writes the name of the variant, or with
+///`#[codec(tag = "index")]` its index as a varuint. The indexes follow the variants from 0 unless
+///set by `#[codec(index = N)]`, like the discriminants. A field marked
+///`#[codec(skip)]` is not encoded and decoded as its default value, one marked `#[codec(default)]`
+///is decoded as its default value when the data ends before it, so that fields can be appended to
+///a type whose values are already stored.
+#[proc_macro_derive(Encoder, attributes(codec))]
 pub fn derive_encoder(item: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(item).unwrap();
-    let name = &ast.ident; //struct name
-    let expanded: proc_macro2::TokenStream = match ast.data {
-        Data::Enum(DataEnum { ref variants, .. }) => {
-            let get_selfs: Vec<_> = variants
-                .iter()
-                .map(|variant| {
-                    let field_name = &variant.ident; // 字段名字
-                    quote! {
-                        #field_name
-                    }
-                })
-                .collect();
-            let implemented_encoder = quote! {
-                  impl ontio_std::abi::Encoder for #name {
-                    fn encode(&self, sink: &mut ontio_std::abi::Sink) {
-                         match self {
-                             #(#name::#get_selfs(temp) => {
-                                sink.write(stringify!(#get_selfs));
-                                sink.write(temp);
-                             }), *
-                         }
-                    }
-                  }
-            };
-            implemented_encoder
-        }
-        Data::Struct(DataStruct { ref fields, .. }) => {
-            if let Fields::Named(ref fields_name) = fields {
-                let get_selfs: Vec<_> = fields_name
-                    .named
-                    .iter()
-                    .map(|field| {
-                        let field_name = field.ident.as_ref().unwrap(); // 字段名字
-                        quote! {
-                            &self.#field_name
-                        }
-                    })
-                    .collect();
-                let implemented_encoder = quote! {
-                    impl ontio_std::abi::Encoder for #name {
-                        fn encode(&self, sink: &mut ontio_std::abi::Sink) {
-                             sink.write((#(#get_selfs),*));
-                        }
-                    }
-                };
-                implemented_encoder
-            } else {
-                panic!("not struct");
-            }
-        }
-        _ => panic!("not support"),
-    };
-    expanded.into()
+    codec::quote_encoder(&ast).unwrap_or_else(|err| err.to_compile_error()).into()
 }
 
-#[proc_macro_derive(Decoder)]
+///Derive `Decoder` for structs and enums, the counterpart of `Encoder`.
+#[proc_macro_derive(Decoder, attributes(codec))]
 pub fn derive_decoder(item: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(item).unwrap();
-    let name = &ast.ident; //struct name
-    let expanded: proc_macro2::TokenStream = match ast.data {
-        Data::Enum(DataEnum { ref variants, .. }) => {
-            let get_selfs: Vec<_> = variants
-                .iter()
-                .map(|variant| {
-                    let field_name = &variant.ident; // 字段名字
-                    let match_name =
-                        syn::Ident::new(&field_name.to_string().to_mixed_case(), field_name.span());
-                    quote! {
-                        #match_name
-                    }
-                })
-                .collect();
-
-            let get_selfs2: Vec<_> = variants
-                .iter()
-                .map(|variant| {
-                    let field_name = &variant.ident; // 字段名字
-                    quote! {
-                        #field_name
-                    }
-                })
-                .collect();
+    codec::quote_decoder(&ast).unwrap_or_else(|err| err.to_compile_error()).into()
+}
 
-            let implemented_decoder = quote! {
-                impl<'a> ontio_std::abi::Decoder<'a> for #name {
-                    fn decode(source: &mut ontio_std::abi::Source<'a>) -> Result<Self,
-                    ontio_std::abi::Error> {
-                    let ty:&str= source.read()?;
-                        match ty {
-                             #(stringify!(#get_selfs) => {
-                                let temp = source.read()?;
-                                Ok(#name::#get_selfs2(temp))
-                             }),*
-                             _ => {
-                             panic!("decoder not support:{}", ty)
-                             }
-                        }
-                    }
-                }
-            };
-            implemented_decoder
-        }
-        Data::Struct(DataStruct { ref fields, .. }) => {
-            if let Fields::Named(ref fields_name) = fields {
-                let get_selfs: Vec<_> = fields_name
-                    .named
-                    .iter()
-                    .map(|field| {
-                        let field_name = field.ident.as_ref().unwrap(); // 字段名字
-                        quote! {
-                            #field_name
-                        }
-                    })
-                    .collect();
-                let implemented_decoder = quote! {
-                    impl<'a> ontio_std::abi::Decoder<'a> for #name {
-                        fn decode(source: &mut ontio_std::abi::Source) -> Result<Self,
-                        ontio_std::abi::Error> {
-                            return Ok(#name {
-                            #(#get_selfs: source.read()?),*
-                            })
-                        }
-                    }
-                };
-                implemented_decoder
-            } else {
-                panic!("not struct");
-            }
-        }
-        _ => panic!("not support"),
-    };
+///Derive `VmValueEncoder` for structs, written as a list of their fields.
+///
+///Tuple and named structs are lists whose values are the fields in order, the fields use the
+///attributes of `Encoder`, a field with `#[codec(default)]` may be missing from the end of the list.
+#[proc_macro_derive(VmValueEncoder, attributes(codec))]
+pub fn derive_vm_value_encoder(item: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(item).unwrap();
+    vm_value::quote_encoder(&ast).unwrap_or_else(|err| err.to_compile_error()).into()
+}
 
-    expanded.into()
+///Derive `VmValueDecoder` for structs, the counterpart of `VmValueEncoder`.
+#[proc_macro_derive(VmValueDecoder, attributes(codec))]
+pub fn derive_vm_value_decoder(item: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(item).unwrap();
+    vm_value::quote_decoder(&ast).unwrap_or_else(|err| err.to_compile_error()).into()
 }
@@ -0,0 +1,102 @@
+use crate::codec::{add_bounds, decoder_generics, parse_fields, Shape};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput};
+
+fn parse_shape(ast: &DeriveInput) -> syn::Result<Shape<'_>> {
+    let name = &ast.ident;
+    match ast.data {
+        Data::Struct(ref data) => {
+            let fields = parse_fields(&data.fields)?;
+            Ok(Shape { path: quote!(#name), tag: quote!(), fields })
+        }
+        _ => Err(syn::Error::new_spanned(name, "only structs are supported as VmValue")),
+    }
+}
+
+pub fn quote_encoder(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &ast.ident;
+    let shape = parse_shape(ast)?;
+    let mut generics = ast.generics.clone();
+    let bound = quote!(ontio_std::abi::VmValueEncoder);
+    add_bounds(&mut generics, core::slice::from_ref(&shape), bound, |f| !f.skip, |_| false);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let pattern = shape.pattern();
+    let writes = shape.fields.iter().filter(|field| !field.attrs.skip).map(|field| {
+        let binding = &field.binding;
+        quote!(builder.write(#binding);)
+    });
+    Ok(quote! {
+        impl #impl_generics ontio_std::abi::VmValueEncoder for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn serialize(&self, builder: &mut ontio_std::abi::VmValueBuilder) {
+                let #pattern = self;
+                builder.list_with(|builder| {
+                    #(#writes)*
+                });
+            }
+        }
+    })
+}
+
+pub fn quote_decoder(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &ast.ident;
+    let shape = parse_shape(ast)?;
+    let (lifetime, mut generics) = decoder_generics(&ast.generics);
+    add_bounds(
+        &mut generics,
+        core::slice::from_ref(&shape),
+        quote!(ontio_std::abi::VmValueDecoder<#lifetime>),
+        |f| !f.skip,
+        |f| f.skip || f.default,
+    );
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = ast.generics.split_for_impl();
+
+    //the fields with `default` may be missing from the end of the list.
+    let encoded: Vec<_> = shape.fields.iter().filter(|field| !field.attrs.skip).collect();
+    let min_len = encoded.iter().filter(|field| !field.attrs.default).count() as u32;
+    let max_len = encoded.len() as u32;
+    let check_len = if min_len == max_len {
+        quote!(len != #max_len)
+    } else if min_len == 0 {
+        quote!(len > #max_len)
+    } else {
+        quote!(!(#min_len..=#max_len).contains(&len))
+    };
+    let path = &shape.path;
+    let mut values = Vec::new();
+    let mut pos = 0u32;
+    for field in shape.fields.iter() {
+        let member = &field.member;
+        if field.attrs.skip {
+            values.push(quote!(#member: ::core::default::Default::default()));
+            continue;
+        }
+        pos += 1;
+        if field.attrs.default {
+            values.push(quote!(#member: if len < #pos {
+                ::core::default::Default::default()
+            } else {
+                parser.read()?
+            }));
+        } else {
+            values.push(quote!(#member: parser.read()?));
+        }
+    }
+
+    Ok(quote! {
+        impl #impl_generics ontio_std::abi::VmValueDecoder<#lifetime> for #name #ty_generics #where_clause {
+            fn deserialize(
+                parser: &mut ontio_std::abi::VmValueParser<#lifetime>,
+            ) -> ::core::result::Result<Self, ontio_std::abi::Error> {
+                let len = parser.list()?;
+                if #check_len {
+                    return ::core::result::Result::Err(ontio_std::abi::Error::LengthInconsistency);
+                }
+                ::core::result::Result::Ok(#path { #(#values),* })
+            }
+        }
+    })
+}
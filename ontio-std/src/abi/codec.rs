@@ -287,14 +287,10 @@ for_each_tuple! {
     ($($item:ident)*) => {
         impl<'a, $($item: VmValueDecoder<'a>),*> VmValueDecoder<'a> for ($($item,)*) {
             fn deserialize(_parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
-                let ty = _parser.source.read_byte()?;
-                if ty != crate::abi::event_builder::TYPE_LIST {
-                     return Err(Error::TypeInconsistency);
-                }
                 #[allow(unused_mut)]
                 let mut count = 0u32;
                 $(let _ :$item; count +=1;)*
-                let l = _parser.source.read_u32()?;
+                let l = _parser.list()?;
                 if l!= count {
                     return Err(Error::LengthInconsistency);
                 }
@@ -303,13 +299,16 @@ for_each_tuple! {
         }
         impl<$($item: VmValueEncoder),*> VmValueEncoder for ($($item,)*) {
             fn serialize(&self, _builder: &mut VmValueBuilder) {
-                _builder.common.sink.write_byte(crate::abi::event_builder::TYPE_LIST);
-                 #[allow(unused_mut)]
-                let mut count = 0u32;
                 #[allow(non_snake_case)]
                 let ($($item,)*) = self;
-                $(let _ = $item;count +=1;)*
-                _builder.common.sink.write_u32(count);
+                _builder.list_with(|_builder| {
+                    $(_builder.write($item);)*
+                });
+            }
+
+            fn serialize_items(&self, _builder: &mut VmValueBuilder) {
+                #[allow(non_snake_case)]
+                let ($($item,)*) = self;
                 $(_builder.write($item);)*
             }
         }
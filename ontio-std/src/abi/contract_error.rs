@@ -0,0 +1,128 @@
+use crate::prelude::*;
+use crate::runtime;
+use core::fmt::Write;
+
+/// Error codes below this value are reserved by `ontio_std`, user defined errors should start from here.
+pub const USER_ERROR_CODE_START: u32 = 1000;
+
+///Error returned from a contract method, the generated dispatcher aborts the execution with it.
+///
+///The error is reported through `runtime::panic` as a json object `{"code":<code>,"message":"<message>"}`,
+///so callers and indexers can tell the failure reason from the code instead of matching panic strings.
+///
+/// # Example
+/// ```no_run
+/// # use ontio_std::abi::{ContractError, USER_ERROR_CODE_START};
+/// # use ontio_std::prelude::*;
+/// enum TokenError {
+///     InsufficientBalance,
+/// }
+///
+/// impl ContractError for TokenError {
+///     fn code(&self) -> u32 {
+///         match self {
+///             TokenError::InsufficientBalance => USER_ERROR_CODE_START,
+///         }
+///     }
+///
+///     fn message(&self) -> String {
+///         match self {
+///             TokenError::InsufficientBalance => "insufficient balance".to_string(),
+///         }
+///     }
+/// }
+/// ```
+pub trait ContractError {
+    fn code(&self) -> u32;
+
+    fn message(&self) -> String;
+
+    ///Encode the error as the structured panic message.
+    fn encode_message(&self) -> String {
+        let message = self.message();
+        let mut buf = String::with_capacity(message.len() + 32);
+        write!(buf, "{{\"code\":{},\"message\":\"", self.code()).unwrap();
+        for c in message.chars() {
+            match c {
+                '"' => buf.push_str("\\\""),
+                '\\' => buf.push_str("\\\\"),
+                '\n' => buf.push_str("\\n"),
+                '\r' => buf.push_str("\\r"),
+                '\t' => buf.push_str("\\t"),
+                c if (c as u32) < 0x20 => write!(buf, "\\u{:04x}", c as u32).unwrap(),
+                c => buf.push(c),
+            }
+        }
+        buf.push_str("\"}");
+        buf
+    }
+
+    ///Abort the contract execution with this error, all writes to the chain will be cancelled.
+    fn revert(&self) -> ! {
+        runtime::panic(&self.encode_message())
+    }
+}
+
+///Errors raised by `ontio_std` itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StdError {
+    ///The input of the contract can not be decoded into the method arguments.
+    BadArgument,
+    ///The invoked method is not defined by the contract.
+    UnknownMethod,
+    ///The required witness is not provided.
+    Unauthorized,
+    ///The output of a called contract can not be decoded into the return type.
+    BadReturnValue,
+}
+
+impl ContractError for StdError {
+    fn code(&self) -> u32 {
+        match self {
+            StdError::BadArgument => 1,
+            StdError::UnknownMethod => 2,
+            StdError::Unauthorized => 3,
+            StdError::BadReturnValue => 4,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            StdError::BadArgument => "failed to decode argument",
+            StdError::UnknownMethod => "invoke unsupported method",
+            StdError::Unauthorized => "check witness failed",
+            StdError::BadReturnValue => "failed to decode return value",
+        }
+        .to_string()
+    }
+}
+
+impl<T: ContractError> ContractError for &T {
+    fn code(&self) -> u32 {
+        (*self).code()
+    }
+
+    fn message(&self) -> String {
+        (*self).message()
+    }
+}
+
+#[test]
+fn test_encode_message() {
+    struct Custom;
+    impl ContractError for Custom {
+        fn code(&self) -> u32 {
+            USER_ERROR_CODE_START + 1
+        }
+
+        fn message(&self) -> String {
+            "say \"hi\"\n\\".to_string()
+        }
+    }
+
+    assert_eq!(
+        StdError::UnknownMethod.encode_message(),
+        r#"{"code":2,"message":"invoke unsupported method"}"#
+    );
+    assert_eq!(Custom.encode_message(), r#"{"code":1001,"message":"say \"hi\"\n\\"}"#);
+}
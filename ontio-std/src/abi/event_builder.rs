@@ -1,6 +1,7 @@
 use super::Sink;
 use crate::prelude::*;
 use crate::runtime;
+use crate::types::U256;
 
 pub(crate) const TYPE_BYTEARRAY: u8 = 0x00;
 pub(crate) const TYPE_STRING: u8 = 0x01;
@@ -8,7 +9,12 @@ pub(crate) const TYPE_ADDRESS: u8 = 0x02;
 pub(crate) const TYPE_BOOL: u8 = 0x03;
 pub(crate) const TYPE_INT: u8 = 0x04;
 pub(crate) const TYPE_H256: u8 = 0x05;
+//The int256, map and struct tags below are extensions of the format: a node whose cross-VM and
+//event decoder does not know them rejects the values carrying them.
+pub(crate) const TYPE_INT256: u8 = 0x06;
 pub(crate) const TYPE_LIST: u8 = 0x10;
+pub(crate) const TYPE_MAP: u8 = 0x11;
+pub(crate) const TYPE_STRUCT: u8 = 0x12;
 
 ///Entity used to push events in a contract.
 #[must_use = "this `EventBuilder` should call notify to take effect"]
@@ -85,6 +91,32 @@ impl EventBuilder {
         self
     }
 
+    ///Push I128 type event in contract, negative numbers included
+    ///# Example
+    ///```no_run
+    /// # use ontio_std::abi::EventBuilder;
+    /// # use ontio_std::types::I128;
+    ///   EventBuilder::new().int(I128::new(-123)).notify();
+    ///```
+    pub fn int(mut self, val: I128) -> Self {
+        self.common.int(val);
+        self
+    }
+
+    ///Push U256 type event in contract
+    ///
+    ///The int256 type needs support from the node, otherwise the event is rejected on chain.
+    ///# Example
+    ///```no_run
+    /// # use ontio_std::abi::EventBuilder;
+    /// # use ontio_std::types::U256;
+    ///   EventBuilder::new().int256(U256::MAX).notify();
+    ///```
+    pub fn int256(mut self, val: U256) -> Self {
+        self.common.int256(val);
+        self
+    }
+
     ///Push bool type event in contract
     ///# Example
     ///```no_run
@@ -155,6 +187,18 @@ impl VmValueBuilderCommon {
         self.num_entry += 1;
     }
 
+    pub fn int(&mut self, val: I128) {
+        self.sink.write_byte(TYPE_INT);
+        self.sink.write_i128(val);
+        self.num_entry += 1;
+    }
+
+    pub fn int256(&mut self, val: U256) {
+        self.sink.write_byte(TYPE_INT256);
+        self.sink.write_bytes(&val.to_le_bytes());
+        self.num_entry += 1;
+    }
+
     pub fn bool(&mut self, b: bool) {
         self.sink.write_byte(TYPE_BOOL);
         self.sink.write_bool(b);
@@ -1,15 +1,19 @@
 mod codec;
+mod contract_error;
 mod sink;
 mod source;
+mod vm_value;
 mod vm_value_builder;
 mod vm_value_codec;
 
+pub use self::contract_error::{ContractError, StdError, USER_ERROR_CODE_START};
 pub use self::sink::Sink;
 pub use self::source::Source;
 use crate::prelude::*;
 pub(crate) mod event_builder;
 pub use event_builder::EventBuilder;
 pub(crate) use event_builder::VmValueBuilderCommon;
+pub use vm_value::VmValue;
 pub use vm_value_builder::{VmValueBuilder, VmValueParser};
 pub use vm_value_codec::VmValueDecoder;
 pub use vm_value_codec::VmValueEncoder;
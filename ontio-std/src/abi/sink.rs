@@ -82,12 +82,11 @@ impl Sink {
         self.write_bytes(&val.to_le_bytes())
     }
 
-    #[allow(unused)]
     pub(crate) fn write_i128(&mut self, val: I128) {
         self.write_bytes(&val.to_le_bytes())
     }
 
-    pub(crate) fn write_varuint(&mut self, val: u64) {
+    pub fn write_varuint(&mut self, val: u64) {
         if val < 0xFD {
             self.write_byte(val as u8);
         } else if val < 0xFFFF {
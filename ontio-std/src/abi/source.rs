@@ -89,15 +89,18 @@ impl<'a> Source<'a> {
     }
     pub fn read_native_address(&mut self) -> Result<&'a Address, Error> {
         let l = self.read_byte()?;
-        assert_eq!(l, 20);
+        if l != 20 {
+            return Err(Error::IrregularData);
+        }
         self.read_address()
     }
 
     pub fn read_native_varuint(&mut self) -> Result<u64, Error> {
         let l = self.read_byte()?;
         let val = self.read_varuint()?;
-        let l_new = varuint_encode_size(val);
-        assert_eq!(l as usize, l_new);
+        if l as usize != varuint_encode_size(val) {
+            return Err(Error::IrregularData);
+        }
         Ok(val)
     }
 
@@ -151,6 +154,21 @@ impl<'a> Source<'a> {
         }
     }
 
+    ///Number of bytes not read yet.
+    /// # Example
+    /// ```
+    /// # use ontio_std::abi::{Source, Sink};
+    ///   let mut sink = Sink::new(0);
+    ///   sink.write(123u32);
+    ///   let mut source = Source::new(sink.bytes());
+    ///   source.read_byte().unwrap();
+    ///   assert_eq!(source.remaining(), 3);
+    /// ```
+    ///
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
     ///Skip specified long bytes.
     /// # Example
     /// ```
@@ -0,0 +1,106 @@
+use super::{Error, VmValueBuilder, VmValueDecoder, VmValueEncoder, VmValueParser};
+use crate::prelude::*;
+use crate::types::U256;
+
+///Value of any type of the VmValue format, as returned by a NeoVM contract.
+///
+///`Int256`, `Struct` and `Map` are extensions of the format, encoding them needs support from
+///the node, otherwise the values are rejected on chain.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum VmValue<'a> {
+    ByteArray(&'a [u8]),
+    String(&'a str),
+    Address(&'a Address),
+    Bool(bool),
+    Int(I128),
+    H256(&'a H256),
+    Int256(U256),
+    List(Vec<VmValue<'a>>),
+    Struct(Vec<VmValue<'a>>),
+    ///pairs of key and value, in the order of the data.
+    Map(Vec<(VmValue<'a>, VmValue<'a>)>),
+}
+
+impl VmValueEncoder for VmValue<'_> {
+    fn serialize(&self, builder: &mut VmValueBuilder) {
+        match self {
+            VmValue::ByteArray(bytes) => builder.bytearray(bytes),
+            VmValue::String(s) => builder.string(s),
+            VmValue::Address(addr) => builder.address(addr),
+            VmValue::Bool(b) => builder.bool(*b),
+            VmValue::Int(val) => builder.int(*val),
+            VmValue::H256(hash) => builder.h256(hash),
+            VmValue::Int256(val) => builder.int256(*val),
+            VmValue::List(values) => builder.list_with(|builder| {
+                values.iter().for_each(|value| builder.write(value));
+            }),
+            VmValue::Struct(values) => builder.struct_with(|builder| {
+                values.iter().for_each(|value| builder.write(value));
+            }),
+            VmValue::Map(entries) => builder.map_with(|builder| {
+                for (key, value) in entries {
+                    builder.write(key);
+                    builder.write(value);
+                }
+            }),
+        }
+    }
+}
+
+impl<'a> VmValueDecoder<'a> for VmValue<'a> {
+    fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
+        parser.value()
+    }
+}
+
+#[test]
+fn test_vm_value() {
+    let (addr, hash) = (Address::repeat_byte(1), H256::repeat_byte(2));
+    let value = VmValue::List(vec![
+        VmValue::String("transfer"),
+        VmValue::Struct(vec![
+            VmValue::Address(&addr),
+            VmValue::Int(I128::new(-1)),
+            VmValue::Int256(U256::MAX),
+            VmValue::Bool(false),
+        ]),
+        VmValue::Map(vec![
+            (VmValue::ByteArray(b"k"), VmValue::List(vec![])),
+            (VmValue::H256(&hash), VmValue::Map(vec![])),
+        ]),
+    ]);
+    let mut builder = VmValueBuilder::new();
+    builder.write(&value);
+    let bytes = builder.bytes();
+    let mut parser = VmValueParser::new(&bytes);
+    assert_eq!(parser.value().unwrap(), VmValue::List(vec![value]));
+    assert!(parser.value().is_err());
+
+    // too deeply nested values are rejected.
+    let mut value = VmValue::Bool(true);
+    for _ in 0..40 {
+        value = VmValue::List(vec![value]);
+    }
+    let mut builder = VmValueBuilder::new();
+    builder.write(&value);
+    let bytes = builder.bytes();
+    assert!(VmValueParser::new(&bytes).value().is_err());
+}
+
+#[test]
+fn test_typed() {
+    use alloc::collections::BTreeMap;
+    let mut map = BTreeMap::new();
+    map.insert("alice", -7i64);
+    map.insert("bob", i64::MAX);
+    let mut builder = VmValueBuilder::new();
+    builder.write((&map, 300u32, U256::from(5u128)));
+    let bytes = builder.bytes();
+    let mut parser = VmValueParser::new(&bytes);
+    let (decoded, small, big): (BTreeMap<&str, i64>, _, U256) = parser.read::<(_,)>().unwrap().0;
+    assert_eq!((decoded, small), (map, 300u32));
+    assert_eq!(big, U256::from(5u128));
+    // out of the range of the type.
+    let mut parser = VmValueParser::new(&bytes);
+    assert!(parser.read::<((BTreeMap<&str, i64>, u8, U256),)>().is_err());
+}
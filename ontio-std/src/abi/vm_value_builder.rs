@@ -1,13 +1,28 @@
 use super::event_builder::{
-    TYPE_ADDRESS, TYPE_BOOL, TYPE_BYTEARRAY, TYPE_H256, TYPE_INT, TYPE_STRING,
+    TYPE_ADDRESS, TYPE_BOOL, TYPE_BYTEARRAY, TYPE_H256, TYPE_INT, TYPE_INT256, TYPE_MAP,
+    TYPE_STRING, TYPE_STRUCT,
 };
 use super::Error;
 use super::Source;
-use super::{VmValueBuilderCommon, VmValueDecoder, VmValueEncoder};
+use super::{VmValue, VmValueBuilderCommon, VmValueDecoder, VmValueEncoder};
 use crate::abi::event_builder::TYPE_LIST;
 use crate::prelude::*;
+use crate::types::U256;
 use core::ops::{Deref, DerefMut};
 
+//nesting limit of the values read by `VmValueParser::value`.
+const MAX_DEPTH: u32 = 32;
+
+//length in the header of a nested value with `num_entry` values, a map counts its pairs.
+fn nested_len(ty: u8, num_entry: u32) -> u32 {
+    if ty == TYPE_MAP {
+        assert!(num_entry.is_multiple_of(2), "map with a key without value");
+        num_entry / 2
+    } else {
+        num_entry
+    }
+}
+
 pub struct VmValueBuilder {
     pub(crate) common: VmValueBuilderCommon,
 }
@@ -21,12 +36,14 @@ impl Default for VmValueBuilder {
 pub struct NestedVmValueBuilder<'a> {
     origin: &'a mut VmValueBuilder,
     current: VmValueBuilderCommon,
+    ty: u8,
 }
 
 impl NestedVmValueBuilder<'_> {
     pub fn finish(self) {
         let mut buf = self.current.sink.into();
-        buf[1..5].copy_from_slice(&self.current.num_entry.to_le_bytes());
+        let len = nested_len(self.ty, self.current.num_entry);
+        buf[1..5].copy_from_slice(&len.to_le_bytes());
         self.origin.common.sink.write_bytes(&buf);
     }
 }
@@ -75,13 +92,69 @@ impl VmValueBuilder {
         self.common.number(amount);
     }
 
-    pub fn list(&mut self) -> NestedVmValueBuilder {
+    pub fn int(&mut self, val: I128) {
+        self.common.int(val);
+    }
+
+    ///Write a U256, the node needs to support the int256 type to decode it.
+    pub fn int256(&mut self, val: U256) {
+        self.common.int256(val);
+    }
+
+    fn nested(&mut self, ty: u8) -> NestedVmValueBuilder<'_> {
         let mut nested = VmValueBuilderCommon::new();
-        nested.sink.write_byte(TYPE_LIST); // list type
+        nested.sink.write_byte(ty);
         nested.sink.write_u32(0); // occupy length
         self.common.num_entry += 1;
 
-        NestedVmValueBuilder { origin: self, current: nested }
+        NestedVmValueBuilder { origin: self, current: nested, ty }
+    }
+
+    pub fn list(&mut self) -> NestedVmValueBuilder<'_> {
+        self.nested(TYPE_LIST)
+    }
+
+    ///Build a map, whose keys and values are pushed in turn.
+    ///
+    ///The node needs to support the map type to decode it.
+    pub fn map(&mut self) -> NestedVmValueBuilder<'_> {
+        self.nested(TYPE_MAP)
+    }
+
+    ///Build a struct, the node needs to support the struct type to decode it.
+    pub fn structure(&mut self) -> NestedVmValueBuilder<'_> {
+        self.nested(TYPE_STRUCT)
+    }
+
+    fn nested_with<F: FnOnce(&mut VmValueBuilder)>(&mut self, ty: u8, values: F) {
+        let mut nested = VmValueBuilder { common: VmValueBuilderCommon::new() };
+        nested.common.sink.write_byte(ty);
+        nested.common.sink.write_u32(0); // occupy length
+        values(&mut nested);
+        let len = nested_len(ty, nested.common.num_entry);
+        let mut buf = nested.common.sink.into();
+        buf[1..5].copy_from_slice(&len.to_le_bytes());
+        self.common.sink.write_bytes(&buf);
+        self.common.num_entry += 1;
+    }
+
+    ///Write a list whose values are written by `values`.
+    pub fn list_with<F: FnOnce(&mut VmValueBuilder)>(&mut self, values: F) {
+        self.nested_with(TYPE_LIST, values)
+    }
+
+    ///Write a map whose keys and values are written in turn by `entries`.
+    ///
+    ///The node needs to support the map type to decode it.
+    pub fn map_with<F: FnOnce(&mut VmValueBuilder)>(&mut self, entries: F) {
+        self.nested_with(TYPE_MAP, entries)
+    }
+
+    ///Write a struct whose fields are written by `fields`.
+    ///
+    ///The node needs to support the struct type to decode it.
+    pub fn struct_with<F: FnOnce(&mut VmValueBuilder)>(&mut self, fields: F) {
+        self.nested_with(TYPE_STRUCT, fields)
     }
 
     pub fn bool(&mut self, b: bool) {
@@ -159,6 +232,105 @@ impl<'a> VmValueParser<'a> {
         }
     }
 
+    ///Signed number, the same type as `number`.
+    pub fn int(&mut self) -> Result<I128, Error> {
+        let ty = self.source.read_byte()?;
+        if ty != TYPE_INT {
+            return Err(Error::TypeInconsistency);
+        }
+        Ok(self.source.read_u128()?.to_i128())
+    }
+
+    pub fn int256(&mut self) -> Result<U256, Error> {
+        let ty = self.source.read_byte()?;
+        if ty != TYPE_INT256 {
+            return Err(Error::TypeInconsistency);
+        }
+        self.source.read_u256()
+    }
+
+    fn nested(&mut self, expected: u8) -> Result<u32, Error> {
+        let ty = self.source.read_byte()?;
+        if ty != expected {
+            return Err(Error::TypeInconsistency);
+        }
+        self.source.read_u32()
+    }
+
+    ///Read the header of a list, returns the number of its values.
+    pub fn list(&mut self) -> Result<u32, Error> {
+        self.nested(TYPE_LIST)
+    }
+
+    ///Read the header of a map, returns the number of its pairs of key and value.
+    pub fn map(&mut self) -> Result<u32, Error> {
+        self.nested(TYPE_MAP)
+    }
+
+    ///Read the header of a struct, returns the number of its fields.
+    pub fn structure(&mut self) -> Result<u32, Error> {
+        self.nested(TYPE_STRUCT)
+    }
+
+    ///Read a value of any type, with the values nested in it.
+    /// # Example
+    /// ```
+    /// # use ontio_std::abi::{VmValue, VmValueBuilder, VmValueParser};
+    /// # use ontio_std::types::I128;
+    ///   let mut builder = VmValueBuilder::new();
+    ///   let mut map = builder.map();
+    ///   map.string("balance");
+    ///   map.int(I128::new(-5));
+    ///   map.finish();
+    ///   let bytes = builder.bytes();
+    ///   let mut parser = VmValueParser::new(&bytes);
+    ///   let values = vec![VmValue::Map(vec![(VmValue::String("balance"), VmValue::Int(I128::new(-5)))])];
+    ///   assert_eq!(parser.value().unwrap(), VmValue::List(values));
+    /// ```
+    pub fn value(&mut self) -> Result<VmValue<'a>, Error> {
+        self.value_at(0)
+    }
+
+    fn value_at(&mut self, depth: u32) -> Result<VmValue<'a>, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::IrregularData);
+        }
+        let ty = self.source.read_byte()?;
+        self.source.backup(1);
+        let value = match ty {
+            TYPE_BYTEARRAY => VmValue::ByteArray(self.bytearray()?),
+            TYPE_STRING => VmValue::String(self.string()?),
+            TYPE_ADDRESS => VmValue::Address(self.address()?),
+            TYPE_BOOL => VmValue::Bool(self.bool()?),
+            TYPE_INT => VmValue::Int(self.int()?),
+            TYPE_H256 => VmValue::H256(self.h256()?),
+            TYPE_INT256 => VmValue::Int256(self.int256()?),
+            TYPE_LIST | TYPE_STRUCT => {
+                let len = self.nested(ty)?;
+                let mut values = Vec::new();
+                for _ in 0..len {
+                    values.push(self.value_at(depth + 1)?);
+                }
+                if ty == TYPE_LIST {
+                    VmValue::List(values)
+                } else {
+                    VmValue::Struct(values)
+                }
+            }
+            TYPE_MAP => {
+                let len = self.map()?;
+                let mut entries = Vec::new();
+                for _ in 0..len {
+                    let key = self.value_at(depth + 1)?;
+                    entries.push((key, self.value_at(depth + 1)?));
+                }
+                VmValue::Map(entries)
+            }
+            _ => return Err(Error::TypeInconsistency),
+        };
+        Ok(value)
+    }
+
     pub fn h256(&mut self) -> Result<&'a H256, Error> {
         let ty = self.source.read_byte()?;
         if ty != TYPE_H256 {
@@ -1,9 +1,18 @@
 use super::Error;
 use crate::abi::{VmValueBuilder, VmValueParser};
 use crate::prelude::*;
+use crate::types::U256;
+use alloc::collections::BTreeMap;
+use core::convert::TryFrom;
 
 pub trait VmValueEncoder {
     fn serialize(&self, sink: &mut VmValueBuilder);
+
+    ///Write `self` as the items of the list being built: each element of a tuple, the other
+    ///values as one item.
+    fn serialize_items(&self, builder: &mut VmValueBuilder) {
+        self.serialize(builder)
+    }
 }
 
 impl VmValueEncoder for &str {
@@ -12,12 +21,24 @@ impl VmValueEncoder for &str {
     }
 }
 
+impl VmValueEncoder for String {
+    fn serialize(&self, builder: &mut VmValueBuilder) {
+        builder.string(self);
+    }
+}
+
 impl VmValueEncoder for &[u8] {
     fn serialize(&self, builder: &mut VmValueBuilder) {
         builder.bytearray(self);
     }
 }
 
+impl VmValueEncoder for Vec<u8> {
+    fn serialize(&self, builder: &mut VmValueBuilder) {
+        builder.bytearray(self);
+    }
+}
+
 impl VmValueEncoder for bool {
     fn serialize(&self, builder: &mut VmValueBuilder) {
         builder.bool(*self);
@@ -36,16 +57,43 @@ impl VmValueEncoder for U128 {
     }
 }
 
+impl VmValueEncoder for I128 {
+    fn serialize(&self, builder: &mut VmValueBuilder) {
+        builder.int(*self);
+    }
+}
+
+impl VmValueEncoder for U256 {
+    fn serialize(&self, builder: &mut VmValueBuilder) {
+        builder.int256(*self);
+    }
+}
+
 impl VmValueEncoder for Address {
     fn serialize(&self, builder: &mut VmValueBuilder) {
         builder.address(self);
     }
 }
 
+impl<K: VmValueEncoder, V: VmValueEncoder> VmValueEncoder for BTreeMap<K, V> {
+    fn serialize(&self, builder: &mut VmValueBuilder) {
+        builder.map_with(|builder| {
+            for (key, value) in self {
+                builder.write(key);
+                builder.write(value);
+            }
+        });
+    }
+}
+
 impl<T: VmValueEncoder> VmValueEncoder for &T {
     fn serialize(&self, builder: &mut VmValueBuilder) {
         (*self).serialize(builder)
     }
+
+    fn serialize_items(&self, builder: &mut VmValueBuilder) {
+        (*self).serialize_items(builder)
+    }
 }
 
 pub trait VmValueDecoder<'a>: Sized {
@@ -58,12 +106,24 @@ impl<'a> VmValueDecoder<'a> for &'a str {
     }
 }
 
+impl<'a> VmValueDecoder<'a> for String {
+    fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
+        parser.string().map(|s| s.to_string())
+    }
+}
+
 impl<'a> VmValueDecoder<'a> for &'a [u8] {
     fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
         parser.bytearray()
     }
 }
 
+impl<'a> VmValueDecoder<'a> for Vec<u8> {
+    fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
+        parser.bytearray().map(|bytes| bytes.to_vec())
+    }
+}
+
 impl<'a> VmValueDecoder<'a> for bool {
     fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
         parser.bool()
@@ -76,20 +136,71 @@ impl<'a> VmValueDecoder<'a> for &'a H256 {
     }
 }
 
+impl<'a> VmValueDecoder<'a> for H256 {
+    fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
+        parser.h256().copied()
+    }
+}
+
 impl<'a> VmValueDecoder<'a> for U128 {
     fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
         parser.number()
     }
 }
 
+impl<'a> VmValueDecoder<'a> for I128 {
+    fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
+        parser.int()
+    }
+}
+
+impl<'a> VmValueDecoder<'a> for U256 {
+    fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
+        parser.int256()
+    }
+}
+
 impl<'a> VmValueDecoder<'a> for &'a Address {
     fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
         parser.address()
     }
 }
 
-impl<'a, T: VmValueDecoder<'a>> VmValueDecoder<'a> for &'a T {
-    fn deserialize(parser: &mut VmValueParser<'a>) -> Result<&'a T, Error> {
-        parser.read()
+impl<'a> VmValueDecoder<'a> for Address {
+    fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
+        parser.address().copied()
     }
 }
+
+impl<'a, K: VmValueDecoder<'a> + Ord, V: VmValueDecoder<'a>> VmValueDecoder<'a> for BTreeMap<K, V> {
+    fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
+        let len = parser.map()?;
+        let mut map = BTreeMap::new();
+        for _ in 0..len {
+            let key = parser.read()?;
+            map.insert(key, parser.read()?);
+        }
+        Ok(map)
+    }
+}
+
+//the integers of other widths are numbers checked to fit on reading.
+macro_rules! impl_vm_value_int {
+    ($($ty:ty),*) => {
+        $(
+            impl VmValueEncoder for $ty {
+                fn serialize(&self, builder: &mut VmValueBuilder) {
+                    builder.int(I128::new(*self as i128));
+                }
+            }
+
+            impl<'a> VmValueDecoder<'a> for $ty {
+                fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
+                    <$ty>::try_from(parser.int()?.raw()).map_err(|_| Error::IrregularData)
+                }
+            }
+        )*
+    };
+}
+
+impl_vm_value_int!(u8, u16, u32, u64, i8, i16, i32, i64);
@@ -182,15 +182,293 @@ pub mod governance {
 }
 
 pub mod neo {
+    use crate::abi::{self, Decoder, Encoder, Error, Sink, Source};
+    use crate::abi::{VmValueBuilder, VmValueDecoder, VmValueEncoder, VmValueParser};
     use crate::prelude::*;
+    use crate::types::i128_to_neo_bytes;
+    use core::convert::TryFrom;
 
+    ///Call the NeoVM contract at `contract_address` with the parameters `param`.
+    ///
+    ///The elements of a tuple are the parameters themselves, so the method and the list of its
+    ///arguments are given as `(method, (args...))`.
+    /// # Example
+    /// ```no_run
+    /// # use ontio_std::contract::neo;
+    /// # use ontio_std::types::{Address, U128};
+    /// # let (contract, from, to) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+    /// let res = neo::call_contract(&contract, ("transfer", (from, to, U128::new(100))));
+    /// ```
     pub fn call_contract<T: crate::abi::VmValueEncoder>(
         contract_address: &Address, param: T,
     ) -> Vec<u8> {
         let mut builder = crate::abi::VmValueBuilder::new();
-        param.serialize(&mut builder);
+        param.serialize_items(&mut builder);
         crate::runtime::call_contract(contract_address, &builder.bytes())
     }
+
+    const BYTEARRAY_TYPE: u8 = 0x00;
+    const BOOLEAN_TYPE: u8 = 0x01;
+    const INTEGER_TYPE: u8 = 0x02;
+    const ARRAY_TYPE: u8 = 0x80;
+    const STRUCT_TYPE: u8 = 0x81;
+    const MAP_TYPE: u8 = 0x82;
+
+    //nesting limit of the deserialized stack items.
+    const MAX_DEPTH: u32 = 32;
+
+    ///Stack item of NeoVM.
+    ///
+    ///`Encoder` and `Decoder` use the serialization of the stack items by NeoVM, the one of the
+    ///storage of NeoVM contracts, while `VmValueEncoder` and `VmValueDecoder` convert from and
+    ///to the VmValues of the cross-VM calls. The strings, addresses and hashes of the VmValues
+    ///are byte arrays of NeoVM.
+    /// # Example
+    /// ```no_run
+    /// # use ontio_std::abi::{Source, VmValueParser};
+    /// # use ontio_std::contract::neo::{self, VmValue};
+    /// # use ontio_std::runtime;
+    /// # use ontio_std::types::{Address, I128};
+    /// # let (contract, key) = (Address::repeat_byte(1), b"key");
+    /// let res = neo::call_contract(&contract, ("get", (&key[..],)));
+    /// let value: VmValue = VmValueParser::new(&res).read().unwrap();
+    /// let stored: VmValue = Source::new(&runtime::storage_read(key).unwrap()).read().unwrap();
+    /// assert_eq!(value, stored);
+    /// ```
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    pub enum VmValue {
+        ByteArray(Vec<u8>),
+        Integer(I128),
+        Boolean(bool),
+        Array(Vec<VmValue>),
+        Struct(Vec<VmValue>),
+        ///pairs of key and value, in the order of the data.
+        Map(Vec<(VmValue, VmValue)>),
+    }
+
+    //NeoVM integers are little endian two's complement in the fewest bytes, zero is empty.
+    fn integer_to_bytes(val: I128) -> Vec<u8> {
+        if val.raw() == 0 {
+            Vec::new()
+        } else {
+            i128_to_neo_bytes(val)
+        }
+    }
+
+    fn integer_from_bytes(bytes: &[u8]) -> Result<I128, Error> {
+        if bytes.len() > 16 {
+            return Err(Error::IrregularData);
+        }
+        let neg = bytes.last().is_some_and(|&b| b >= 0x80);
+        let mut buf = if neg { [0xff; 16] } else { [0; 16] };
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Ok(I128::from_le_bytes(buf))
+    }
+
+    impl Encoder for VmValue {
+        fn encode(&self, sink: &mut Sink) {
+            match self {
+                VmValue::ByteArray(bytes) => {
+                    sink.write_byte(BYTEARRAY_TYPE);
+                    sink.write(bytes.as_slice());
+                }
+                VmValue::Integer(val) => {
+                    sink.write_byte(INTEGER_TYPE);
+                    sink.write(integer_to_bytes(*val));
+                }
+                VmValue::Boolean(b) => {
+                    sink.write_byte(BOOLEAN_TYPE);
+                    sink.write_bool(*b);
+                }
+                VmValue::Array(items) | VmValue::Struct(items) => {
+                    let ty = if let VmValue::Array(_) = self { ARRAY_TYPE } else { STRUCT_TYPE };
+                    sink.write_byte(ty);
+                    sink.write(items.as_slice());
+                }
+                VmValue::Map(entries) => {
+                    sink.write_byte(MAP_TYPE);
+                    sink.write_varuint(entries.len() as u64);
+                    for (key, value) in entries {
+                        sink.write(key);
+                        sink.write(value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn decode_at(source: &mut Source, depth: u32) -> Result<VmValue, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::IrregularData);
+        }
+        let value = match source.read_byte()? {
+            BYTEARRAY_TYPE => VmValue::ByteArray(source.read_bytes()?.to_vec()),
+            INTEGER_TYPE => VmValue::Integer(integer_from_bytes(source.read_bytes()?)?),
+            BOOLEAN_TYPE => VmValue::Boolean(source.read_bool()?),
+            ty @ (ARRAY_TYPE | STRUCT_TYPE) => {
+                let len = source.read_varuint()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(decode_at(source, depth + 1)?);
+                }
+                if ty == ARRAY_TYPE {
+                    VmValue::Array(items)
+                } else {
+                    VmValue::Struct(items)
+                }
+            }
+            MAP_TYPE => {
+                let len = source.read_varuint()?;
+                let mut entries = Vec::new();
+                for _ in 0..len {
+                    let key = decode_at(source, depth + 1)?;
+                    entries.push((key, decode_at(source, depth + 1)?));
+                }
+                VmValue::Map(entries)
+            }
+            _ => return Err(Error::TypeInconsistency),
+        };
+        Ok(value)
+    }
+
+    impl<'a> Decoder<'a> for VmValue {
+        fn decode(source: &mut Source<'a>) -> Result<Self, Error> {
+            decode_at(source, 0)
+        }
+    }
+
+    impl VmValueEncoder for VmValue {
+        fn serialize(&self, builder: &mut VmValueBuilder) {
+            match self {
+                VmValue::ByteArray(bytes) => builder.bytearray(bytes),
+                VmValue::Integer(val) => builder.int(*val),
+                VmValue::Boolean(b) => builder.bool(*b),
+                VmValue::Array(items) => builder.list_with(|builder| {
+                    items.iter().for_each(|item| builder.write(item));
+                }),
+                VmValue::Struct(items) => builder.struct_with(|builder| {
+                    items.iter().for_each(|item| builder.write(item));
+                }),
+                VmValue::Map(entries) => builder.map_with(|builder| {
+                    for (key, value) in entries {
+                        builder.write(key);
+                        builder.write(value);
+                    }
+                }),
+            }
+        }
+    }
+
+    impl<'a> TryFrom<abi::VmValue<'a>> for VmValue {
+        type Error = Error;
+
+        fn try_from(value: abi::VmValue<'a>) -> Result<Self, Error> {
+            let items = |values: Vec<abi::VmValue<'a>>| {
+                values.into_iter().map(VmValue::try_from).collect::<Result<Vec<_>, _>>()
+            };
+            let value = match value {
+                abi::VmValue::ByteArray(bytes) => VmValue::ByteArray(bytes.to_vec()),
+                abi::VmValue::String(s) => VmValue::ByteArray(s.as_bytes().to_vec()),
+                abi::VmValue::Address(addr) => VmValue::ByteArray(addr.as_bytes().to_vec()),
+                abi::VmValue::H256(hash) => VmValue::ByteArray(hash.as_bytes().to_vec()),
+                abi::VmValue::Bool(b) => VmValue::Boolean(b),
+                abi::VmValue::Int(val) => VmValue::Integer(val),
+                abi::VmValue::Int256(val) => {
+                    let bytes = val.to_le_bytes();
+                    if bytes[15] >= 0x80 || bytes[16..].iter().any(|&b| b != 0) {
+                        return Err(Error::IrregularData);
+                    }
+                    VmValue::Integer(integer_from_bytes(&bytes[..16])?)
+                }
+                abi::VmValue::List(values) => VmValue::Array(items(values)?),
+                abi::VmValue::Struct(values) => VmValue::Struct(items(values)?),
+                abi::VmValue::Map(entries) => {
+                    let mut map = Vec::new();
+                    for (key, value) in entries {
+                        map.push((VmValue::try_from(key)?, VmValue::try_from(value)?));
+                    }
+                    VmValue::Map(map)
+                }
+            };
+            Ok(value)
+        }
+    }
+
+    impl<'a> VmValueDecoder<'a> for VmValue {
+        fn deserialize(parser: &mut VmValueParser<'a>) -> Result<Self, Error> {
+            VmValue::try_from(parser.value()?)
+        }
+    }
+
+    #[test]
+    fn test_vm_value() {
+        let value = VmValue::Struct(vec![
+            VmValue::ByteArray(b"transfer".to_vec()),
+            VmValue::Array(vec![VmValue::Integer(I128::new(0)), VmValue::Integer(I128::new(-129))]),
+            VmValue::Map(vec![(VmValue::Boolean(true), VmValue::Integer(I128::new(128)))]),
+        ]);
+        let mut sink = Sink::new(16);
+        sink.write(&value);
+        let expected = [
+            0x81, 3, 0x00, 8, b't', b'r', b'a', b'n', b's', b'f', b'e', b'r', 0x80, 2, 0x02, 0,
+            0x02, 2, 0x7f, 0xff, 0x82, 1, 0x01, 1, 0x02, 2, 0x80, 0x00,
+        ];
+        assert_eq!(sink.bytes(), &expected[..]);
+        assert_eq!(Source::new(sink.bytes()).read::<VmValue>().unwrap(), value);
+        assert!(Source::new(&[0x02, 17, 1]).read::<VmValue>().is_err());
+
+        let mut builder = VmValueBuilder::new();
+        builder.write(&value);
+        let mut nested = builder.list();
+        nested.string("ont");
+        nested.address(&Address::repeat_byte(1));
+        nested.finish();
+        builder.int256(crate::types::U256::MAX);
+        let bytes = builder.bytes();
+        let mut parser = VmValueParser::new(&bytes);
+        assert_eq!(parser.list().unwrap(), 3);
+        assert_eq!(parser.read::<VmValue>().unwrap(), value);
+        let address = VmValue::ByteArray(Address::repeat_byte(1).as_bytes().to_vec());
+        let strings = VmValue::Array(vec![VmValue::ByteArray(b"ont".to_vec()), address]);
+        assert_eq!(parser.read::<VmValue>().unwrap(), strings);
+        // out of the range of the NeoVM integers.
+        assert!(parser.read::<VmValue>().is_err());
+    }
+
+    #[test]
+    fn test_call_contract() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        let handle = crate::mock::build_runtime();
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let captured = calls.clone();
+        handle.on_contract_call(move |_, data| {
+            captured.borrow_mut().push(data.to_vec());
+            Vec::new()
+        });
+        let (contract, from) = (Address::repeat_byte(1), Address::repeat_byte(2));
+
+        // `[method, [args]]`, as the call built by hand.
+        call_contract(&contract, ("balanceOf", (&from,)));
+        let mut builder = VmValueBuilder::new();
+        builder.string("balanceOf");
+        let mut nested = builder.list();
+        nested.address(&from);
+        nested.finish();
+        assert_eq!(calls.borrow()[0], builder.bytes());
+        let expected = [
+            &[0, 0x10, 2, 0, 0, 0, 0x01, 9, 0, 0, 0][..],
+            b"balanceOf",
+            &[0x10, 1, 0, 0, 0, 0x02],
+            from.as_bytes(),
+        ]
+        .concat();
+        assert_eq!(calls.borrow()[0], expected);
+
+        call_contract(&contract, "name");
+        let expected = [&[0, 0x10, 1, 0, 0, 0, 0x01, 4, 0, 0, 0][..], b"name"].concat();
+        assert_eq!(calls.borrow()[1], expected);
+    }
 }
 
 pub mod eth {
@@ -219,7 +497,8 @@ pub mod eth {
 
 pub mod ontid {
     use super::super::types::u128_to_neo_bytes;
-    use crate::abi::{Decoder, Encoder, Error, Sink, Source};
+    use crate as ontio_std;
+    use crate::abi::{Decoder, Encoder, Sink};
     use crate::macros::base58;
     use crate::prelude::*;
     use crate::runtime;
@@ -227,73 +506,25 @@ pub mod ontid {
     const VERSION: u8 = 0;
     const ONTID_CONTRACT_ADDRESS: Address = base58!("AFmseVrdL9f9oyCzZefL9tG6Ubvho7BUwN");
 
+    #[derive(Encoder, Decoder)]
     pub struct DDOAttribute {
         key: Vec<u8>,
         value: Vec<u8>,
         value_type: Vec<u8>,
     }
 
-    impl Encoder for DDOAttribute {
-        fn encode(&self, sink: &mut Sink) {
-            sink.write(self.key.as_slice());
-            sink.write(self.value.as_slice());
-            sink.write(self.value_type.as_slice());
-        }
-    }
-
-    impl<'a> Decoder<'a> for DDOAttribute {
-        fn decode(source: &mut Source<'a>) -> Result<Self, Error> {
-            let key: &[u8] = source.read().unwrap();
-            let value: &[u8] = source.read().unwrap();
-            let value_type: &[u8] = source.read().unwrap();
-            Ok(DDOAttribute {
-                key: key.to_vec(),
-                value: value.to_vec(),
-                value_type: value_type.to_vec(),
-            })
-        }
-    }
-
+    #[derive(Encoder, Decoder)]
     pub struct Signer {
         id: Vec<u8>,
         index: u32,
     }
 
-    impl Encoder for Signer {
-        fn encode(&self, sink: &mut Sink) {
-            sink.write(self.id.as_slice());
-            sink.write(self.index);
-        }
-    }
-
-    impl<'a> Decoder<'a> for Signer {
-        fn decode(source: &mut Source<'a>) -> Result<Self, Error> {
-            let id: &[u8] = source.read().unwrap();
-            let index: u32 = source.read().unwrap();
-            Ok(Signer { id: id.to_vec(), index })
-        }
-    }
-
+    #[derive(Encoder, Decoder)]
     pub struct Group {
         pub members: Vec<Vec<u8>>,
         pub threshold: u32,
     }
 
-    impl Encoder for Group {
-        fn encode(&self, sink: &mut Sink) {
-            sink.write(self.members.as_slice());
-            sink.write(self.threshold);
-        }
-    }
-
-    impl<'a> Decoder<'a> for Group {
-        fn decode(source: &mut Source<'a>) -> Result<Self, Error> {
-            let members: Vec<Vec<u8>> = source.read().unwrap();
-            let threshold: u32 = source.read().unwrap();
-            Ok(Group { members, threshold })
-        }
-    }
-
     fn serialize_group(group: &Group) -> Vec<u8> {
         let mut sink = Sink::new(64);
         sink.write_var_bytes(u128_to_neo_bytes(U128::new(group.members.len() as u128)).as_slice());
@@ -391,7 +622,7 @@ pub mod ont {
     use crate::macros::base58;
     use crate::prelude::*;
 
-    const ONT_CONTRACT_ADDRESS: Address = base58!("AFmseVrdL9f9oyCzZefL9tG6UbvhUMqNMV");
+    pub const ONT_CONTRACT_ADDRESS: Address = base58!("AFmseVrdL9f9oyCzZefL9tG6UbvhUMqNMV");
 
     ///Transfer method of ont assets, Transfer ont assets from the from address to the to address
     /// # Example
@@ -608,7 +839,7 @@ pub mod ong {
     use crate::macros::base58;
     use crate::types::{Address, U128};
 
-    const ONG_CONTRACT_ADDRESS: Address = base58!("AFmseVrdL9f9oyCzZefL9tG6UbvhfRZMHJ");
+    pub const ONG_CONTRACT_ADDRESS: Address = base58!("AFmseVrdL9f9oyCzZefL9tG6UbvhfRZMHJ");
 
     ///Transfer method of ong assets, Transfer ont assets from the from address to the to address
     /// # Example
@@ -0,0 +1,152 @@
+use crate::runtime;
+use crate::types::{Address, H256};
+use ripemd160::{Digest, Ripemd160};
+use tiny_keccak::{Hasher, Keccak};
+
+mod signature;
+mod sm2;
+
+pub use self::signature::{
+    address_from_pubkey, verify, verify_ed25519, verify_secp256k1, verify_secp256r1, verify_sm2,
+    KeyType,
+};
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "host-crypto")] {
+        mod env {
+            extern "C-unwind" {
+                pub fn ontio_keccak256(data: *const u8, len: u32, val: *mut u8);
+                pub fn ontio_ripemd160(data: *const u8, len: u32, val: *mut u8);
+                pub fn ontio_verify_signature(
+                    key_type: u32, pubkey: *const u8, pubkey_len: u32, msg: *const u8,
+                    msg_len: u32, sig: *const u8, sig_len: u32,
+                ) -> u32;
+            }
+        }
+
+        fn keccak256_impl(data: &[u8]) -> H256 {
+            let mut hash = H256::zero();
+            unsafe {
+                env::ontio_keccak256(data.as_ptr(), data.len() as u32, hash.as_mut_ptr());
+            }
+            hash
+        }
+
+        fn ripemd160_impl(data: &[u8]) -> [u8; 20] {
+            let mut hash = [0; 20];
+            unsafe {
+                env::ontio_ripemd160(data.as_ptr(), data.len() as u32, hash.as_mut_ptr());
+            }
+            hash
+        }
+    } else {
+        use self::soft::keccak256 as keccak256_impl;
+        use self::soft::ripemd160 as ripemd160_impl;
+    }
+}
+
+//pure rust implementations, also backing the host functions of the mock runtime.
+#[cfg_attr(all(feature = "host-crypto", not(feature = "mock")), allow(unused))]
+pub(crate) mod soft {
+    #[cfg(feature = "mock")]
+    pub(crate) use super::signature::verify_soft as verify_signature;
+    use super::*;
+
+    pub(crate) fn keccak256(data: &[u8]) -> H256 {
+        let mut hash = H256::zero();
+        let mut keccak = Keccak::v256();
+        keccak.update(data);
+        keccak.finalize(hash.as_mut());
+        hash
+    }
+
+    pub(crate) fn ripemd160(data: &[u8]) -> [u8; 20] {
+        let mut hash = [0; 20];
+        hash.copy_from_slice(&Ripemd160::digest(data));
+        hash
+    }
+}
+
+///Keccak-256 hash, as used by ethereum.
+///
+///It is computed in the contract, or by the host with the `host-crypto` feature.
+/// # Example
+/// ```no_run
+/// # use ontio_std::crypto;
+/// let hash = crypto::keccak256("transfer(address,uint256)");
+/// ```
+pub fn keccak256(data: impl AsRef<[u8]>) -> H256 {
+    keccak256_impl(data.as_ref())
+}
+
+///RIPEMD-160 hash.
+///
+///It is computed in the contract, or by the host with the `host-crypto` feature.
+pub fn ripemd160(data: impl AsRef<[u8]>) -> [u8; 20] {
+    ripemd160_impl(data.as_ref())
+}
+
+///Selector of a solidity function, the first 4 bytes of the keccak-256 hash of its signature.
+/// # Example
+/// ```no_run
+/// # use ontio_std::crypto;
+/// assert_eq!(crypto::selector("transfer(address,uint256)"), [0xa9, 0x05, 0x9c, 0xbb]);
+/// ```
+pub fn selector(signature: impl AsRef<[u8]>) -> [u8; 4] {
+    let mut selector = [0; 4];
+    selector.copy_from_slice(&keccak256(signature)[..4]);
+    selector
+}
+
+///Address of a verification script on ontology, the RIPEMD-160 hash of its sha256 hash.
+pub fn address_from_script(script: impl AsRef<[u8]>) -> Address {
+    Address::from(ripemd160(runtime::sha256(script)))
+}
+
+///Ethereum address of an uncompressed secp256k1 public key, given as the 64 bytes of the
+///coordinates, optionally prefixed by `0x04`.
+pub fn eth_address(pubkey: &[u8]) -> Option<Address> {
+    let coordinates = match pubkey {
+        [0x04, rest @ ..] if rest.len() == 64 => rest,
+        _ if pubkey.len() == 64 => pubkey,
+        _ => return None,
+    };
+    Some(Address::from_slice(&keccak256(coordinates)[12..]))
+}
+
+#[test]
+fn test_hash() {
+    use hexutil::read_hex;
+    crate::mock::build_runtime();
+    let hash = keccak256("");
+    assert_eq!(
+        hash.as_bytes(),
+        read_hex("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470").unwrap()
+    );
+    assert_eq!(
+        ripemd160("abc").to_vec(),
+        read_hex("8eb208f7e05d987a9b044a8e98c6b087f15a0bfc").unwrap()
+    );
+    assert_eq!(selector("transfer(address,uint256)"), [0xa9, 0x05, 0x9c, 0xbb]);
+
+    // the public key of the private key 1.
+    let pubkey = read_hex(concat!(
+        "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"
+    ))
+    .unwrap();
+    let addr = eth_address(&pubkey).unwrap();
+    assert_eq!(addr.as_bytes(), read_hex("7e5f4552091a69125d5dfcb7b8c2659029395bdf").unwrap());
+    assert_eq!(eth_address(&[&[4], pubkey.as_slice()].concat()), Some(addr));
+    assert_eq!(eth_address(&pubkey[1..]), None);
+
+    // BIP173: the hash of the compressed secp256k1 public key of the private key 1.
+    let pubkey = read_hex("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798");
+    let addr = address_from_script(pubkey.unwrap());
+    assert_eq!(addr.as_bytes(), read_hex("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap());
+
+    // the checksig script of the secp256r1 public key of the private key 1, hashed with OpenSSL.
+    let script = read_hex("21036b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296ac");
+    let addr = crate::macros::base58!("AR6NuGFzZfzqbXR3YasfXNmR3VHVNKi2yo");
+    assert_eq!(address_from_script(script.unwrap()), addr);
+}
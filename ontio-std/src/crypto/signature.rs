@@ -0,0 +1,261 @@
+use super::{address_from_script, sm2};
+use crate::prelude::*;
+use crate::types::Address;
+#[cfg(feature = "soft-crypto")]
+use core::convert::{TryFrom, TryInto};
+
+const OPCODE_CHECKSIG: u8 = 0xac;
+
+//key type and curve label prefixing the serialized public keys other than secp256r1.
+const KEY_TYPE_ECDSA: u8 = 0x12;
+const KEY_TYPE_SM2: u8 = 0x13;
+const KEY_TYPE_EDDSA: u8 = 0x14;
+const CURVE_SECP256K1: u8 = 5;
+const CURVE_SM2P256V1: u8 = 20;
+const CURVE_ED25519: u8 = 25;
+
+///Public key types of ontology accounts.
+///
+///The ECDSA and SM2 public keys are given in the SEC1 compressed or uncompressed encoding, the
+///Ed25519 public keys as their 32 bytes. The signatures are the 64 bytes `r || s` for ECDSA and
+///SM2, and the 64 bytes signature for Ed25519.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyType {
+    ///ECDSA over secp256r1 with sha256, the default key type of ontology accounts.
+    Secp256r1 = 0,
+    ///ECDSA over secp256k1 with sha256.
+    Secp256k1 = 1,
+    Ed25519 = 2,
+    ///SM2 with SM3 and the default user id `1234567812345678`.
+    Sm2 = 3,
+}
+
+impl KeyType {
+    pub fn from_u8(val: u8) -> Option<KeyType> {
+        match val {
+            0 => Some(KeyType::Secp256r1),
+            1 => Some(KeyType::Secp256k1),
+            2 => Some(KeyType::Ed25519),
+            3 => Some(KeyType::Sm2),
+            _ => None,
+        }
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "host-crypto")] {
+        fn verify_impl(key_type: KeyType, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+            unsafe {
+                super::env::ontio_verify_signature(
+                    key_type as u32,
+                    pubkey.as_ptr(),
+                    pubkey.len() as u32,
+                    msg.as_ptr(),
+                    msg.len() as u32,
+                    sig.as_ptr(),
+                    sig.len() as u32,
+                ) != 0
+            }
+        }
+    } else if #[cfg(feature = "soft-crypto")] {
+        use self::verify_soft as verify_impl;
+    } else {
+        compile_error!("signature verification requires the `soft-crypto` or `host-crypto` feature");
+    }
+}
+
+#[cfg(feature = "soft-crypto")]
+#[cfg_attr(all(feature = "host-crypto", not(feature = "mock")), allow(unused))]
+pub(crate) fn verify_soft(key_type: KeyType, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    match key_type {
+        KeyType::Secp256r1 => {
+            use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+            match (VerifyingKey::from_sec1_bytes(pubkey), Signature::from_slice(sig)) {
+                (Ok(key), Ok(sig)) => key.verify(msg, &sig).is_ok(),
+                _ => false,
+            }
+        }
+        KeyType::Secp256k1 => {
+            use k256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+            match (VerifyingKey::from_sec1_bytes(pubkey), Signature::from_slice(sig)) {
+                (Ok(key), Ok(sig)) => key.verify(msg, &sig).is_ok(),
+                _ => false,
+            }
+        }
+        KeyType::Ed25519 => {
+            use ed25519_dalek::{Signature, VerifyingKey};
+            let key = match <&[u8; 32]>::try_from(pubkey) {
+                Ok(key) => VerifyingKey::from_bytes(key),
+                Err(_) => return false,
+            };
+            match (key, Signature::from_slice(sig)) {
+                (Ok(key), Ok(sig)) => key.verify_strict(msg, &sig).is_ok(),
+                _ => false,
+            }
+        }
+        KeyType::Sm2 => sm2::verify(pubkey, msg, sig, sm2::DEFAULT_ID),
+    }
+}
+
+///Verify the signature `sig` of `msg` by `pubkey`, see `KeyType` for the encodings.
+///
+///It is computed in the contract with the default `soft-crypto` feature, or by the host with the
+///`host-crypto` feature. The contracts using the host can disable the default features not to
+///build the pure rust implementations.
+/// # Example
+/// ```no_run
+/// # use ontio_std::crypto::{self, KeyType};
+/// # let (pubkey, price, sig) = (vec![0u8; 33], b"ONT:1.02", vec![0u8; 64]);
+/// assert!(crypto::verify(KeyType::Secp256r1, &pubkey, price, &sig));
+/// ```
+pub fn verify(key_type: KeyType, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    verify_impl(key_type, pubkey, msg, sig)
+}
+
+pub fn verify_secp256r1(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    verify(KeyType::Secp256r1, pubkey, msg, sig)
+}
+
+pub fn verify_secp256k1(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    verify(KeyType::Secp256k1, pubkey, msg, sig)
+}
+
+pub fn verify_ed25519(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    verify(KeyType::Ed25519, pubkey, msg, sig)
+}
+
+pub fn verify_sm2(pubkey: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    verify(KeyType::Sm2, pubkey, msg, sig)
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "soft-crypto")] {
+        //compressed encoding of a secp256r1 or secp256k1 public key, `None` if not on the curve.
+        fn compress_ecdsa(key_type: KeyType, pubkey: &[u8]) -> Option<Vec<u8>> {
+            let key = if key_type == KeyType::Secp256r1 {
+                let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(pubkey).ok()?;
+                key.to_encoded_point(true).as_bytes().to_vec()
+            } else {
+                let key = k256::ecdsa::VerifyingKey::from_sec1_bytes(pubkey).ok()?;
+                key.to_encoded_point(true).as_bytes().to_vec()
+            };
+            Some(key)
+        }
+
+        fn check_ed25519(pubkey: &[u8]) -> Option<()> {
+            ed25519_dalek::VerifyingKey::from_bytes(pubkey.try_into().ok()?).ok().map(|_| ())
+        }
+    } else {
+        //without the curve arithmetic, only the encoding of the key is checked.
+        fn compress_ecdsa(_key_type: KeyType, pubkey: &[u8]) -> Option<Vec<u8>> {
+            match pubkey {
+                [0x02 | 0x03, x @ ..] if x.len() == 32 => Some(pubkey.to_vec()),
+                [0x04, xy @ ..] if xy.len() == 64 => Some([&[2 | (xy[63] & 1)], &xy[..32]].concat()),
+                _ => None,
+            }
+        }
+
+        fn check_ed25519(pubkey: &[u8]) -> Option<()> {
+            if pubkey.len() == 32 {
+                Some(())
+            } else {
+                None
+            }
+        }
+    }
+}
+
+///Public key serialized as by ontology, `None` if invalid.
+fn serialize_pubkey(key_type: KeyType, pubkey: &[u8]) -> Option<Vec<u8>> {
+    let serialized = match key_type {
+        KeyType::Secp256r1 => compress_ecdsa(key_type, pubkey)?,
+        KeyType::Secp256k1 => {
+            let key = compress_ecdsa(key_type, pubkey)?;
+            [&[KEY_TYPE_ECDSA, CURVE_SECP256K1], key.as_slice()].concat()
+        }
+        KeyType::Ed25519 => {
+            check_ed25519(pubkey)?;
+            [&[KEY_TYPE_EDDSA, CURVE_ED25519], pubkey].concat()
+        }
+        KeyType::Sm2 => [&[KEY_TYPE_SM2, CURVE_SM2P256V1][..], &sm2::compress(pubkey)?].concat(),
+    };
+    Some(serialized)
+}
+
+///Address of the ontology account of a public key, `None` if the key is invalid.
+///
+///The address is the one of the script checking a signature of the key, so a contract can
+///authorize the signers of off-chain messages as it does the accounts. Without the `soft-crypto`
+///feature, only the encoding of the secp256r1, secp256k1 and Ed25519 keys is checked.
+pub fn address_from_pubkey(key_type: KeyType, pubkey: &[u8]) -> Option<Address> {
+    let key = serialize_pubkey(key_type, pubkey)?;
+    let script = [&[key.len() as u8], key.as_slice(), &[OPCODE_CHECKSIG]].concat();
+    Some(address_from_script(script))
+}
+
+#[test]
+fn test_verify() {
+    use crate::macros::base58;
+    use ed25519_dalek::Signer as _;
+    crate::mock::build_runtime();
+    let msg = b"ONT:1.02";
+
+    let key = p256::ecdsa::SigningKey::from_slice(&[1; 32]).unwrap();
+    let sig: p256::ecdsa::Signature = key.sign(msg);
+    let pubkey = key.verifying_key().to_encoded_point(true);
+    assert!(verify_secp256r1(pubkey.as_bytes(), msg, &sig.to_bytes()));
+    let uncompressed = key.verifying_key().to_encoded_point(false);
+    assert!(verify(KeyType::Secp256r1, uncompressed.as_bytes(), msg, &sig.to_bytes()));
+    assert!(!verify_secp256r1(pubkey.as_bytes(), b"ONT:9.99", &sig.to_bytes()));
+    assert!(!verify_secp256k1(pubkey.as_bytes(), msg, &sig.to_bytes()));
+
+    let key = k256::ecdsa::SigningKey::from_slice(&[2; 32]).unwrap();
+    let sig: k256::ecdsa::Signature = key.sign(msg);
+    let pubkey = key.verifying_key().to_encoded_point(false);
+    assert!(verify_secp256k1(pubkey.as_bytes(), msg, &sig.to_bytes()));
+    assert!(!verify_secp256k1(pubkey.as_bytes(), msg, &sig.to_bytes()[1..]));
+
+    let key = ed25519_dalek::SigningKey::from_bytes(&[3; 32]);
+    let sig = key.sign(msg).to_bytes();
+    let pubkey = key.verifying_key().to_bytes();
+    assert!(verify_ed25519(&pubkey, msg, &sig));
+    assert!(!verify_ed25519(&pubkey, b"", &sig));
+    assert!(!verify_ed25519(&pubkey[1..], msg, &sig));
+
+    // the addresses of the secp256r1 and secp256k1 public keys of the private key 1, and of the
+    // ed25519 key of the first test vector of RFC 8032, hashed and encoded with OpenSSL.
+    let vectors = [
+        (
+            KeyType::Secp256r1,
+            concat!(
+                "046b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+                "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5"
+            ),
+            base58!("AR6NuGFzZfzqbXR3YasfXNmR3VHVNKi2yo"),
+        ),
+        (
+            KeyType::Secp256r1,
+            "036b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+            base58!("AR6NuGFzZfzqbXR3YasfXNmR3VHVNKi2yo"),
+        ),
+        (
+            KeyType::Secp256k1,
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            base58!("AKFC9JWgx1HFedoHD41eL7Z4XizyZX4xdX"),
+        ),
+        (
+            KeyType::Ed25519,
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            base58!("ASjQhNcHoTJkVHnyr2KdDLmFmWZtEbg9Ws"),
+        ),
+    ];
+    for (key_type, pubkey, addr) in vectors.iter() {
+        let pubkey = hexutil::read_hex(pubkey).unwrap();
+        assert_eq!(address_from_pubkey(*key_type, &pubkey), Some(*addr));
+    }
+
+    let invalid = [&[2], &[0xff; 32][..]].concat();
+    assert!(!verify_sm2(&invalid, msg, &[1; 64]));
+    assert_eq!(address_from_pubkey(KeyType::Sm2, &invalid), None);
+    assert_eq!(KeyType::from_u8(KeyType::Sm2 as u8), Some(KeyType::Sm2));
+}
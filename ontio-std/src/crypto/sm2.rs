@@ -0,0 +1,433 @@
+//SM2 signature verification over the recommended curve sm2p256v1, with the SM3 hash.
+use crate::prelude::*;
+
+#[allow(clippy::all)]
+mod uint {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+use self::uint::U256;
+
+//user id of the signer when none is given, as specified by GM/T 0009.
+pub(crate) const DEFAULT_ID: &[u8] = b"1234567812345678";
+
+const P: &str = "FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF00000000FFFFFFFFFFFFFFFF";
+const A: &str = "FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF00000000FFFFFFFFFFFFFFFC";
+const B: &str = "28E9FA9E9D9F5E344D5A9E4BCF6509A7F39789F515AB8F92DDBCBD414D940E93";
+const N: &str = "FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFF7203DF6B21C6052B53BBF40939D54123";
+const GX: &str = "32C4AE2C1F1981195F9904466A39C9948FE30BBFF2660BE1715A4589334C74C7";
+const GY: &str = "BC3736A2F4F6779C59BDCEE36B692153D0A9877CC62A474002DF32E52139F0A0";
+
+fn hex(val: &str) -> U256 {
+    let digits: Vec<u8> = val.bytes().map(|c| (c as char).to_digit(16).unwrap() as u8).collect();
+    let bytes: Vec<u8> = digits.chunks(2).map(|pair| pair[0] << 4 | pair[1]).collect();
+    U256::from_big_endian(&bytes)
+}
+
+fn to_bytes(val: U256) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    val.to_big_endian(&mut bytes);
+    bytes
+}
+
+fn add_mod(a: U256, b: U256, m: U256) -> U256 {
+    let (sum, overflow) = a.overflowing_add(b);
+    if overflow || sum >= m {
+        sum.overflowing_sub(m).0
+    } else {
+        sum
+    }
+}
+
+fn sub_mod(a: U256, b: U256, m: U256) -> U256 {
+    if a >= b {
+        a - b
+    } else {
+        m - (b - a)
+    }
+}
+
+//arithmetic modulo the odd `m` with the elements in montgomery form `x * 2^256 mod m`, which
+//avoids a division by `m` in each multiplication.
+struct Field {
+    m: U256,
+    //-m^-1 mod 2^64.
+    m_inv: u64,
+    //2^512 mod m, to convert into montgomery form.
+    r2: U256,
+}
+
+impl Field {
+    fn new(m: U256) -> Field {
+        //newton iteration, each step doubles the number of correct low bits.
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2u64.wrapping_sub(m.0[0].wrapping_mul(inv)));
+        }
+        //2^256 mod m for m > 2^255, doubled 256 times.
+        let mut r2 = U256::zero().overflowing_sub(m).0;
+        for _ in 0..256 {
+            r2 = add_mod(r2, r2, m);
+        }
+        Field { m, m_inv: inv.wrapping_neg(), r2 }
+    }
+
+    //a * b / 2^256 mod m.
+    fn mul(&self, a: U256, b: U256) -> U256 {
+        let (m, a, b) = (&self.m.0, &a.0, &b.0);
+        let mut t = [0u64; 6];
+        for &bi in b.iter() {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let val = t[j] as u128 + a[j] as u128 * bi as u128 + carry;
+                t[j] = val as u64;
+                carry = val >> 64;
+            }
+            let val = t[4] as u128 + carry;
+            t[4] = val as u64;
+            t[5] = (val >> 64) as u64;
+
+            let k = t[0].wrapping_mul(self.m_inv);
+            let mut carry = (t[0] as u128 + k as u128 * m[0] as u128) >> 64;
+            for j in 1..4 {
+                let val = t[j] as u128 + k as u128 * m[j] as u128 + carry;
+                t[j - 1] = val as u64;
+                carry = val >> 64;
+            }
+            let val = t[4] as u128 + carry;
+            t[3] = val as u64;
+            t[4] = t[5] + (val >> 64) as u64;
+        }
+        let res = U256([t[0], t[1], t[2], t[3]]);
+        if t[4] != 0 || res >= self.m {
+            res.overflowing_sub(self.m).0
+        } else {
+            res
+        }
+    }
+
+    fn add(&self, a: U256, b: U256) -> U256 {
+        add_mod(a, b, self.m)
+    }
+
+    fn sub(&self, a: U256, b: U256) -> U256 {
+        sub_mod(a, b, self.m)
+    }
+
+    fn to_mont(&self, val: U256) -> U256 {
+        self.mul(val, self.r2)
+    }
+
+    fn out_of_mont(&self, val: U256) -> U256 {
+        self.mul(val, U256::one())
+    }
+
+    fn pow(&self, base: U256, exp: U256) -> U256 {
+        let mut res = self.to_mont(U256::one());
+        for i in (0..exp.bits()).rev() {
+            res = self.mul(res, res);
+            if exp.bit(i) {
+                res = self.mul(res, base);
+            }
+        }
+        res
+    }
+
+    //inverse modulo the prime `m`.
+    fn inv(&self, val: U256) -> U256 {
+        self.pow(val, self.m - 2)
+    }
+}
+
+//the coordinates and curve coefficients are kept in montgomery form.
+struct Curve {
+    fp: Field,
+    a: U256,
+    b: U256,
+    n: U256,
+    g: Point,
+}
+
+//point in jacobian coordinates, the point at infinity has `z == 0`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Point {
+    x: U256,
+    y: U256,
+    z: U256,
+}
+
+impl Point {
+    const INFINITY: Point = Point { x: U256([0; 4]), y: U256([0; 4]), z: U256([0; 4]) };
+}
+
+impl Curve {
+    fn sm2() -> Curve {
+        let fp = Field::new(hex(P));
+        let (a, b) = (fp.to_mont(hex(A)), fp.to_mont(hex(B)));
+        let g =
+            Point { x: fp.to_mont(hex(GX)), y: fp.to_mont(hex(GY)), z: fp.to_mont(U256::one()) };
+        Curve { fp, a, b, n: hex(N), g }
+    }
+
+    fn affine(&self, (x, y): (U256, U256)) -> Point {
+        let fp = &self.fp;
+        Point { x: fp.to_mont(x), y: fp.to_mont(y), z: fp.to_mont(U256::one()) }
+    }
+
+    //y^2 of the point with abscissa `x`.
+    fn rhs(&self, x: U256) -> U256 {
+        let fp = &self.fp;
+        let x3 = fp.mul(fp.mul(x, x), x);
+        fp.add(fp.add(x3, fp.mul(self.a, x)), self.b)
+    }
+
+    fn double(&self, pt: &Point) -> Point {
+        if pt.z.is_zero() || pt.y.is_zero() {
+            return Point::INFINITY;
+        }
+        let fp = &self.fp;
+        let twice = |val: U256| fp.add(val, val);
+        let y2 = fp.mul(pt.y, pt.y);
+        let s = twice(twice(fp.mul(pt.x, y2)));
+        let z2 = fp.mul(pt.z, pt.z);
+        let xx = fp.mul(pt.x, pt.x);
+        let m = fp.add(fp.add(twice(xx), xx), fp.mul(self.a, fp.mul(z2, z2)));
+        let x = fp.sub(fp.mul(m, m), twice(s));
+        let y = fp.sub(fp.mul(m, fp.sub(s, x)), twice(twice(twice(fp.mul(y2, y2)))));
+        let z = twice(fp.mul(pt.y, pt.z));
+        Point { x, y, z }
+    }
+
+    fn add_points(&self, p1: &Point, p2: &Point) -> Point {
+        if p1.z.is_zero() {
+            return *p2;
+        }
+        if p2.z.is_zero() {
+            return *p1;
+        }
+        let fp = &self.fp;
+        let z1z1 = fp.mul(p1.z, p1.z);
+        let z2z2 = fp.mul(p2.z, p2.z);
+        let u1 = fp.mul(p1.x, z2z2);
+        let u2 = fp.mul(p2.x, z1z1);
+        let s1 = fp.mul(p1.y, fp.mul(p2.z, z2z2));
+        let s2 = fp.mul(p2.y, fp.mul(p1.z, z1z1));
+        if u1 == u2 {
+            return if s1 == s2 { self.double(p1) } else { Point::INFINITY };
+        }
+        let h = fp.sub(u2, u1);
+        let r = fp.sub(s2, s1);
+        let hh = fp.mul(h, h);
+        let hhh = fp.mul(hh, h);
+        let u1hh = fp.mul(u1, hh);
+        let x = fp.sub(fp.sub(fp.mul(r, r), hhh), fp.add(u1hh, u1hh));
+        let y = fp.sub(fp.mul(r, fp.sub(u1hh, x)), fp.mul(s1, hhh));
+        let z = fp.mul(h, fp.mul(p1.z, p2.z));
+        Point { x, y, z }
+    }
+
+    fn scalar_mul(&self, k: U256, pt: &Point) -> Point {
+        let mut res = Point::INFINITY;
+        for i in (0..k.bits()).rev() {
+            res = self.double(&res);
+            if k.bit(i) {
+                res = self.add_points(&res, pt);
+            }
+        }
+        res
+    }
+
+    //affine coordinates out of montgomery form.
+    fn to_affine(&self, pt: &Point) -> Option<(U256, U256)> {
+        if pt.z.is_zero() {
+            return None;
+        }
+        let fp = &self.fp;
+        let zinv = fp.inv(pt.z);
+        let zinv2 = fp.mul(zinv, zinv);
+        let (x, y) = (fp.mul(pt.x, zinv2), fp.mul(pt.y, fp.mul(zinv2, zinv)));
+        Some((fp.out_of_mont(x), fp.out_of_mont(y)))
+    }
+
+    //public key in the SEC1 compressed or uncompressed encoding.
+    fn decode_point(&self, key: &[u8]) -> Option<(U256, U256)> {
+        let fp = &self.fp;
+        let (x, y) = match key {
+            [0x04, coordinates @ ..] if coordinates.len() == 64 => {
+                let x = U256::from_big_endian(&coordinates[..32]);
+                let y = U256::from_big_endian(&coordinates[32..]);
+                (x, y)
+            }
+            [prefix @ (0x02 | 0x03), x @ ..] if x.len() == 32 => {
+                let x = U256::from_big_endian(x);
+                if x >= fp.m {
+                    return None;
+                }
+                //p = 3 mod 4, the square root is a power of (p + 1) / 4.
+                let y = fp.out_of_mont(fp.pow(self.rhs(fp.to_mont(x)), (fp.m + 1) >> 2));
+                let y = if y.bit(0) == (*prefix == 0x03) { y } else { fp.sub(U256::zero(), y) };
+                (x, y)
+            }
+            _ => return None,
+        };
+        if x >= fp.m || y >= fp.m {
+            return None;
+        }
+        let (xm, ym) = (fp.to_mont(x), fp.to_mont(y));
+        if fp.mul(ym, ym) != self.rhs(xm) {
+            return None;
+        }
+        Some((x, y))
+    }
+}
+
+///Compressed encoding of a SM2 public key, `None` if invalid.
+pub(crate) fn compress(key: &[u8]) -> Option<Vec<u8>> {
+    let (x, y) = Curve::sm2().decode_point(key)?;
+    let prefix = if y.bit(0) { 0x03 } else { 0x02 };
+    Some([&[prefix], &to_bytes(x)[..]].concat())
+}
+
+//digest of the message signed by the owner of the public key (x, y) with user id `id`.
+fn digest(curve: &Curve, id: &[u8], (x, y): (U256, U256), msg: &[u8]) -> U256 {
+    let mut data = ((id.len() * 8) as u16).to_be_bytes().to_vec();
+    data.extend_from_slice(id);
+    for val in [hex(A), hex(B), hex(GX), hex(GY), x, y] {
+        data.extend_from_slice(&to_bytes(val));
+    }
+    let za = sm3(&data);
+    U256::from_big_endian(&sm3(&[&za[..], msg].concat())) % curve.n
+}
+
+///Verify the SM2 signature `r || s` of `msg` by `key` with user id `id`.
+pub(crate) fn verify(key: &[u8], msg: &[u8], sig: &[u8], id: &[u8]) -> bool {
+    let curve = Curve::sm2();
+    let (point, sig) = match (curve.decode_point(key), sig.len()) {
+        (Some(point), 64) => (point, sig),
+        _ => return false,
+    };
+    let (r, s) = (U256::from_big_endian(&sig[..32]), U256::from_big_endian(&sig[32..]));
+    if r.is_zero() || s.is_zero() || r >= curve.n || s >= curve.n {
+        return false;
+    }
+    let e = digest(&curve, id, point, msg);
+    let t = add_mod(r, s, curve.n);
+    if t.is_zero() {
+        return false;
+    }
+    let sg = curve.scalar_mul(s, &curve.g);
+    let tp = curve.scalar_mul(t, &curve.affine(point));
+    match curve.to_affine(&curve.add_points(&sg, &tp)) {
+        Some((x1, _)) => add_mod(e, x1 % curve.n, curve.n) == r,
+        None => false,
+    }
+}
+
+///SM3 hash.
+pub(crate) fn sm3(data: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x7380166f, 0x4914b2b9, 0x172442d7, 0xda8a0600, 0xa96f30bc, 0x163138aa, 0xe38dee4d,
+        0xb0fb0e4e,
+    ];
+    let mut msg = data.to_vec();
+    msg.push(0x80);
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+    msg.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+    for block in msg.chunks(64) {
+        compress_block(&mut state, block);
+    }
+    let mut hash = [0; 32];
+    for (i, word) in state.iter().enumerate() {
+        hash[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    hash
+}
+
+fn compress_block(state: &mut [u32; 8], block: &[u8]) {
+    let p0 = |x: u32| x ^ x.rotate_left(9) ^ x.rotate_left(17);
+    let p1 = |x: u32| x ^ x.rotate_left(15) ^ x.rotate_left(23);
+    let mut w = [0u32; 68];
+    for (i, word) in block.chunks(4).enumerate() {
+        w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    for j in 16..68 {
+        w[j] = p1(w[j - 16] ^ w[j - 9] ^ w[j - 3].rotate_left(15))
+            ^ w[j - 13].rotate_left(7)
+            ^ w[j - 6];
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for j in 0..64 {
+        let t: u32 = if j < 16 { 0x79cc4519 } else { 0x7a879d8a };
+        let ss1 = a.rotate_left(12).wrapping_add(e).wrapping_add(t.rotate_left(j as u32 % 32));
+        let ss1 = ss1.rotate_left(7);
+        let ss2 = ss1 ^ a.rotate_left(12);
+        let (ff, gg) = if j < 16 {
+            (a ^ b ^ c, e ^ f ^ g)
+        } else {
+            ((a & b) | (a & c) | (b & c), (e & f) | (!e & g))
+        };
+        let tt1 = ff.wrapping_add(d).wrapping_add(ss2).wrapping_add(w[j] ^ w[j + 4]);
+        let tt2 = gg.wrapping_add(h).wrapping_add(ss1).wrapping_add(w[j]);
+        d = c;
+        c = b.rotate_left(9);
+        b = a;
+        a = tt1;
+        h = g;
+        g = f.rotate_left(19);
+        f = e;
+        e = p0(tt2);
+    }
+    for (word, val) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word ^= val;
+    }
+}
+
+#[test]
+fn test_sm3() {
+    use hexutil::read_hex;
+    let expect = read_hex("66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0");
+    assert_eq!(sm3(b"abc").to_vec(), expect.unwrap());
+    let expect = read_hex("debe9ff92275b8a138604889c18e5a4d6fdb70e5387e5765293dcba39c0c5732");
+    assert_eq!(sm3(&b"abcd".repeat(16)).to_vec(), expect.unwrap());
+}
+
+#[test]
+fn test_sm2() {
+    use hexutil::read_hex;
+    let curve = Curve::sm2();
+    assert_eq!(curve.scalar_mul(curve.n, &curve.g).z, U256::zero());
+
+    // the example of GB/T 32918.2 with the recommended curve, checked with OpenSSL.
+    let key = read_hex(concat!(
+        "0409f9df311e5421a150dd7d161e4bc5c672179fad1833fc076bb08ff356f35020",
+        "ccea490ce26775a52dc6ea718cc1aa600aed05fbf35e084a6632f6072da9ad13"
+    ))
+    .unwrap();
+    let sig = read_hex(concat!(
+        "f5a03b0648d2c4630eeac513e1bb81a15944da3827d5b74143ac7eaceee720b3",
+        "b1b6aa29df212fd8763182bc0d421ca1bb9038fd1f7f42d4840b69c485bbc1aa"
+    ))
+    .unwrap();
+    let compressed = compress(&key).unwrap();
+    assert_eq!(compressed, [&[3], &key[1..33]].concat());
+    assert_eq!(curve.decode_point(&compressed), curve.decode_point(&key));
+    assert!(verify(&key, b"message digest", &sig, DEFAULT_ID));
+    assert!(verify(&compressed, b"message digest", &sig, DEFAULT_ID));
+    assert!(!verify(&compressed, b"message digesT", &sig, DEFAULT_ID));
+    assert!(!verify(&compressed, b"message digest", &sig, b"ALICE123@YAHOO.COM"));
+    assert!(!verify(&compressed, b"message digest", &sig[1..], DEFAULT_ID));
+    assert!(!verify(&compressed[1..], b"message digest", &sig, DEFAULT_ID));
+
+    // signed by `openssl pkeyutl -sign -rawin -digest sm3 -pkeyopt distid:1234567812345678`.
+    let sig = read_hex(concat!(
+        "6ec5ecc4d8c5ce921100dc86cffc79c1800beae771cb5414a837341aa679ce6a",
+        "578fca4472d65bba6e2530a63c65b2bea77822d25bf173344c13636263ea3591"
+    ))
+    .unwrap();
+    assert!(verify(&key, b"message digest", &sig, DEFAULT_ID));
+    assert!(!verify(&key, b"message", &sig, DEFAULT_ID));
+}
@@ -2,18 +2,99 @@ use crate::abi::{Decoder, Encoder, Error, Sink, Source};
 use crate::database;
 use crate::prelude::*;
 use alloc::collections::BTreeMap;
+use alloc::vec;
 use cmp::PartialEq;
+use core::ops::{Bound, RangeBounds};
 
-//slice default size
+//max size of a bucket, a full bucket is split in two.
 const INDEX_SIZE: u32 = 64;
+//a bucket smaller than this is merged with its neighbour.
+const MERGE_SIZE: u32 = INDEX_SIZE / 4;
+//max number of buckets kept in cache.
+const CACHE_SIZE: usize = 8;
+
+//Fenwick tree over the bucket sizes, for locating an index in logarithmic time.
+struct PrefixSum {
+    tree: Vec<u32>,
+}
+
+impl PrefixSum {
+    fn build(sizes: &[(u32, u32)]) -> Self {
+        let mut tree: Vec<u32> = Vec::with_capacity(sizes.len() + 1);
+        tree.push(0);
+        let mut sum = PrefixSum { tree };
+        for &(_, size) in sizes {
+            sum.push(size);
+        }
+        sum
+    }
+
+    fn len(&self) -> usize {
+        self.tree.len() - 1
+    }
+
+    //sum of the sizes of the first `pos` buckets.
+    fn prefix(&self, mut pos: usize) -> u32 {
+        let mut sum = 0;
+        while pos > 0 {
+            sum += self.tree[pos];
+            pos &= pos - 1;
+        }
+        sum
+    }
+
+    fn push(&mut self, size: u32) {
+        let i = self.len() + 1;
+        let lowbit = i & i.wrapping_neg();
+        let node = size + self.prefix(i - 1) - self.prefix(i - lowbit);
+        self.tree.push(node);
+    }
+
+    fn add(&mut self, pos: usize, delta: i32) {
+        let mut i = pos + 1;
+        while i < self.tree.len() {
+            self.tree[i] = self.tree[i].wrapping_add(delta as u32);
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    //keep the first `len` buckets, a node only covers the buckets before it.
+    fn truncate(&mut self, len: usize) {
+        self.tree.truncate(len + 1);
+    }
+
+    //the bucket containing `index` and the index of its first element.
+    fn find(&self, index: u32) -> (usize, u32) {
+        let mut pos = 0;
+        let mut rem = index;
+        let mut step = self.len().checked_next_power_of_two().unwrap_or(0);
+        while step > 0 {
+            if pos + step <= self.len() && self.tree[pos + step] <= rem {
+                pos += step;
+                rem -= self.tree[pos];
+            }
+            step >>= 1;
+        }
+        (pos, index - rem)
+    }
+}
+
+struct Bucket<T> {
+    items: Vec<T>,
+    dirty: bool,
+    last_used: u64,
+}
 
 pub struct ListStore<T: Encoder> {
     key: Vec<u8>,
-    need_flush: Vec<u32>, //index,store all index which slice need update
     size: u32,
     next_key_id: u32,
-    index_size: Vec<(u32, u32)>,  //index, count
-    cache: BTreeMap<u32, Vec<T>>, //index, vec
+    index_size: Vec<(u32, u32)>, //index, count
+    prefix_sum: PrefixSum,
+    cache: BTreeMap<u32, Bucket<T>>, //index, bucket
+    removed: Vec<u32>,               //index of the buckets to delete on flush
+    header_dirty: bool,
+    tick: u64,
 }
 
 impl<T: Encoder> Drop for ListStore<T> {
@@ -28,20 +109,20 @@ where
 {
     #[allow(unused)]
     pub(crate) fn contains(&mut self, value: &T) -> bool {
-        if self.size == 0 {
-            return false;
-        } else {
-            for i in 0..self.size {
-                if let Some(data) = self.get(i) {
-                    if data == value {
-                        return true;
-                    }
-                }
+        for &(id, _) in self.index_size.iter() {
+            let found = match self.cache.get(&id) {
+                Some(bucket) => bucket.items.contains(value),
+                //scan without caching, not to evict the buckets in use.
+                None => read_bucket::<T>(&self.key, id).contains(value),
+            };
+            if found {
+                return true;
             }
         }
         false
     }
 }
+
 impl<T: Encoder> ListStore<T> {
     fn encode(&self, sink: &mut Sink) {
         sink.write(self.next_key_id);
@@ -49,6 +130,20 @@ impl<T: Encoder> ListStore<T> {
     }
 }
 
+fn read_bucket<T>(key: &[u8], id: u32) -> Vec<T>
+where
+    for<'a> T: Decoder<'a>,
+{
+    let data: Vec<u8> = database::get(gen_key(key, id)).unwrap();
+    let mut source = Source::new(&data);
+    let l = source.read_u32().unwrap();
+    let mut items: Vec<T> = Vec::with_capacity(l as usize);
+    for _ in 0..l {
+        items.push(source.read().unwrap());
+    }
+    items
+}
+
 impl<T> ListStore<T>
 where
     for<'a> T: Decoder<'a> + Encoder,
@@ -59,20 +154,31 @@ where
         let total = index_size.iter().map(|(_key, size)| size).sum();
         Ok(ListStore {
             key,
-            need_flush: Vec::new(), //index,store all index which slice need update
             size: total,
             next_key_id,
-            index_size,             //index, count
-            cache: BTreeMap::new(), //index, vec
+            prefix_sum: PrefixSum::build(&index_size),
+            index_size,
+            cache: BTreeMap::new(),
+            removed: Vec::new(),
+            header_dirty: false,
+            tick: 0,
         })
     }
 
     pub(crate) fn new(key: Vec<u8>) -> ListStore<T> {
-        let need_flush: Vec<u32> = Vec::default();
-        let index_size: Vec<(u32, u32)> = Vec::new();
-        let cache: BTreeMap<u32, Vec<T>> = BTreeMap::new();
-        ListStore { key, need_flush, size: 0, next_key_id: 0, index_size, cache }
+        ListStore {
+            key,
+            size: 0,
+            next_key_id: 0,
+            index_size: Vec::new(),
+            prefix_sum: PrefixSum::build(&[]),
+            cache: BTreeMap::new(),
+            removed: Vec::new(),
+            header_dirty: false,
+            tick: 0,
+        }
     }
+
     pub fn open(key: &[u8]) -> ListStore<T> {
         match database::get::<_, Vec<u8>>(key) {
             None => ListStore::new(key.to_vec()),
@@ -91,6 +197,112 @@ where
         self.len() == 0
     }
 
+    //the bucket at `pos` of the index, loaded into cache.
+    fn bucket(&mut self, pos: usize) -> &mut Bucket<T> {
+        let id = self.index_size[pos].0;
+        self.tick += 1;
+        if !self.cache.contains_key(&id) {
+            if self.cache.len() >= CACHE_SIZE {
+                self.evict();
+            }
+            let items = read_bucket(&self.key, id);
+            self.cache.insert(id, Bucket { items, dirty: false, last_used: 0 });
+        }
+        let bucket = self.cache.get_mut(&id).unwrap();
+        bucket.last_used = self.tick;
+        bucket
+    }
+
+    //the bucket at `pos` to be modified.
+    fn bucket_mut(&mut self, pos: usize) -> &mut Vec<T> {
+        let bucket = self.bucket(pos);
+        bucket.dirty = true;
+        &mut bucket.items
+    }
+
+    //drop the least recently used bucket from cache, writing it back if modified.
+    fn evict(&mut self) {
+        let lru = self.cache.iter().min_by_key(|(_, bucket)| bucket.last_used).map(|(&id, _)| id);
+        if let Some(id) = lru {
+            let bucket = self.cache.remove(&id).unwrap();
+            if bucket.dirty {
+                write_bucket(&self.key, id, &bucket.items);
+            }
+        }
+    }
+
+    fn resize_bucket(&mut self, pos: usize, delta: i32) {
+        let count = &mut self.index_size[pos].1;
+        *count = (*count as i32 + delta) as u32;
+        self.size = (self.size as i32 + delta) as u32;
+        self.prefix_sum.add(pos, delta);
+        self.header_dirty = true;
+    }
+
+    fn locate(&self, index: u32) -> (usize, usize) {
+        let (pos, start) = self.prefix_sum.find(index);
+        (pos, (index - start) as usize)
+    }
+
+    fn new_bucket(&mut self, pos: usize, items: Vec<T>) {
+        let id = self.next_key_id;
+        self.next_key_id += 1;
+        self.index_size.insert(pos, (id, items.len() as u32));
+        if pos + 1 == self.index_size.len() {
+            self.prefix_sum.push(items.len() as u32);
+        } else {
+            self.prefix_sum = PrefixSum::build(&self.index_size);
+        }
+        self.size += items.len() as u32;
+        self.header_dirty = true;
+        if self.cache.len() >= CACHE_SIZE {
+            self.evict();
+        }
+        self.tick += 1;
+        self.cache.insert(id, Bucket { items, dirty: true, last_used: self.tick });
+    }
+
+    fn remove_bucket(&mut self, pos: usize) -> Vec<T> {
+        let items = core::mem::take(&mut self.bucket(pos).items);
+        let (id, count) = self.index_size.remove(pos);
+        self.cache.remove(&id);
+        self.removed.push(id);
+        self.prefix_sum = PrefixSum::build(&self.index_size);
+        self.size -= count;
+        self.header_dirty = true;
+        items
+    }
+
+    fn split(&mut self, pos: usize) {
+        let half = self.index_size[pos].1 / 2;
+        let tail = self.bucket_mut(pos).split_off(half as usize);
+        let moved = tail.len() as i32;
+        self.resize_bucket(pos, -moved);
+        self.new_bucket(pos + 1, tail);
+    }
+
+    //merge the bucket at `pos` with a neighbour if they fit in one bucket.
+    fn merge(&mut self, pos: usize) -> bool {
+        let count = self.index_size[pos].1;
+        if count == 0 {
+            self.remove_bucket(pos);
+            return true;
+        }
+        let fits = |other: &(u32, u32)| count + other.1 <= INDEX_SIZE;
+        let (first, second) = if self.index_size.get(pos + 1).is_some_and(fits) {
+            (pos, pos + 1)
+        } else if pos > 0 && fits(&self.index_size[pos - 1]) {
+            (pos - 1, pos)
+        } else {
+            return false;
+        };
+        let items = self.remove_bucket(second);
+        let moved = items.len() as i32;
+        self.bucket_mut(first).extend(items);
+        self.resize_bucket(first, moved);
+        true
+    }
+
     pub fn pop(&mut self) -> Option<T> {
         if self.size == 0 {
             None
@@ -102,239 +314,284 @@ where
     pub fn remove(&mut self, index: u32) -> T {
         if index >= self.size {
             panic!("[remove] index out of bound");
-        } else {
-            let mut end = 0;
-            let ind = self
-                .index_size
-                .iter()
-                .take_while(|&x| {
-                    end += x.1;
-                    index >= end
-                })
-                .count();
-            let mut bulk = &mut self.index_size[ind];
-            let start = end - bulk.1;
-            //if data in cache
-            let val = if let Some(x) = self.cache.get_mut(&bulk.0) {
-                x.remove((index - start) as usize)
-            } else {
-                //read data from database
-                let key = gen_key(self.key.as_slice(), bulk.0);
-
-                let data: Vec<u8> = database::get(key).unwrap();
-                let mut source = Source::new(&data);
-                let l = source.read_u32().unwrap();
-                let mut temp: Vec<T> = Vec::new();
-                for _ in 0..l {
-                    temp.push(source.read().unwrap());
-                }
-                let val = temp.remove((index - start) as usize);
-                self.cache.insert(bulk.0, temp);
-                val
-            };
-            //update need_flush
-            if !self.need_flush.contains(&bulk.0) {
-                self.need_flush.push(bulk.0);
-            }
-            //update index_size
-            bulk.1 -= 1;
-            //update list size
-            self.size -= 1;
-            val
         }
+        let (pos, offset) = self.locate(index);
+        let val = self.bucket_mut(pos).remove(offset);
+        self.resize_bucket(pos, -1);
+        if self.index_size[pos].1 < MERGE_SIZE {
+            self.merge(pos);
+        }
+        val
     }
 
     pub fn push(&mut self, payload: T) {
-        //if null list
-        if self.index_size.is_empty() {
-            //update cache
-            self.cache.insert(0, vec![payload]);
-            //update index_count
-            self.index_size.push((0, 1));
-            self.need_flush.push(0);
-            self.next_key_id += 1;
-        } else {
-            //update the last index_count and the last key->data
-            let mut last_index_count = self.index_size.last_mut().unwrap();
-            //if data not in cache
-            let keyn: Vec<u8> = gen_key(self.key.as_slice(), last_index_count.0);
-            self.cache.entry(last_index_count.0).or_insert_with(|| {
-                //read data from database
-                let last_node_vec_data: Vec<u8> = database::get(keyn.as_slice()).unwrap();
-                let mut source = Source::new(&last_node_vec_data);
-                let last_length = source.read_u32().unwrap();
-                let mut last_node_vec: Vec<T> = Vec::new();
-                for _ in 0..last_length {
-                    last_node_vec.push(source.read().unwrap());
-                }
-                last_node_vec
-            });
-            //if data in cache
-            if let Some(last_node_vec) = self.cache.get_mut(&last_index_count.0) {
-                //if the slice is filled
-                let l = last_node_vec.len() as u32;
-                if l >= INDEX_SIZE {
-                    //cache add new k->v
-                    self.cache.insert(self.next_key_id, vec![payload]);
-                    self.index_size.push((self.next_key_id, 1));
-                    self.need_flush.push(self.next_key_id);
-                    self.next_key_id += 1;
-                } else {
-                    //if slice is not filled
-                    last_node_vec.push(payload);
-                    //update need_flush
-                    if !self.need_flush.contains(&last_index_count.0) {
-                        self.need_flush.push(last_index_count.0);
-                    }
-                    //update index_count
-                    last_index_count.1 += 1
-                }
+        match self.index_size.last() {
+            Some(&(_, count)) if count < INDEX_SIZE => {
+                let pos = self.index_size.len() - 1;
+                self.bucket_mut(pos).push(payload);
+                self.resize_bucket(pos, 1);
             }
+            _ => self.new_bucket(self.index_size.len(), vec![payload]),
         }
-        self.size += 1;
     }
 
     pub fn insert(&mut self, index: u32, payload: T) {
-        if index >= self.size {
+        if index > self.size {
             panic!("index out of bound");
-        } else {
-            let end = &mut 0;
-            let ind = self
-                .index_size
-                .iter()
-                .take_while(|&x| {
-                    *end += x.1;
-                    *end <= index
-                })
-                .count();
-            let bulk = &mut self.index_size[ind];
-            let start = *end - bulk.1;
-            if self.cache.contains_key(&bulk.0) {
-                //if data in cache
-                let temp = self.cache.get_mut(&bulk.0).unwrap();
-                temp.insert((index - start) as usize, payload);
-            } else {
-                //read data from db
-                let key: Vec<u8> = gen_key(self.key.as_slice(), bulk.0);
-                match database::get::<_, Vec<u8>>(key.as_slice()) {
-                    Some(data) => {
-                        let mut source = Source::new(&data);
-                        let l = source.read_u32().unwrap();
-                        let mut temp: Vec<T> = Vec::new();
-                        for _ in 0..l {
-                            temp.push(source.read().unwrap());
-                        }
-                        temp.insert((index - start) as usize, payload);
-                        self.cache.insert(index, temp);
-                    }
-                    None => unreachable!(),
-                }
-            }
-            bulk.1 += 1;
-            self.size += 1;
+        } else if index == self.size {
+            return self.push(payload);
+        }
+        let (pos, offset) = self.locate(index);
+        self.bucket_mut(pos).insert(offset, payload);
+        self.resize_bucket(pos, 1);
+        if self.index_size[pos].1 > INDEX_SIZE {
+            self.split(pos);
         }
     }
 
     pub fn clear(&mut self) {
-        let index_size = self.index_size.to_vec();
-        for bulk in index_size {
-            let key: Vec<u8> = gen_key(self.key.as_slice(), bulk.0);
-            database::delete(key.as_slice());
-        }
-        self.need_flush.clear();
-        self.next_key_id = 0;
+        //the ids keep increasing, a new bucket written back by `evict` must not reuse the id of a
+        //bucket deleted on flush.
+        self.removed.extend(self.index_size.iter().map(|&(id, _)| id));
         self.index_size.clear();
+        self.prefix_sum = PrefixSum::build(&[]);
         self.size = 0;
         self.cache.clear();
-    }
-
-    pub fn iter(&mut self) -> Iterator<T> {
-        Iterator::new(0, self)
+        self.header_dirty = true;
     }
 
     pub fn get(&mut self, index: u32) -> Option<&T> {
         if index >= self.size {
             panic!("[get] index out of bound")
         }
-        let mut end = 0;
-        let ind = self
-            .index_size
-            .iter()
-            .take_while(|&x| {
-                end += x.1;
-                end <= index
-            })
-            .count();
-        let bulk = &self.index_size[ind];
-        let start = end - bulk.1;
-        //if data not in cache, read data from database
-        if self.cache.get(&bulk.0).is_none() {
-            let key: Vec<u8> = gen_key(self.key.as_slice(), bulk.0);
-            let data: Vec<u8> = database::get(key.as_slice()).unwrap();
-            let mut source = Source::new(&data);
-            let l = source.read_u32().unwrap();
-            let mut temp: Vec<T> = Vec::new();
-            for _ in 0..l {
-                temp.push(source.read().unwrap());
+        let (pos, offset) = self.locate(index);
+        self.bucket(pos).items.get(offset)
+    }
+
+    ///Mutable access to the element at `index`, which is written back on flush.
+    pub fn get_mut(&mut self, index: u32) -> Option<&mut T> {
+        if index >= self.size {
+            return None;
+        }
+        let (pos, offset) = self.locate(index);
+        self.bucket_mut(pos).get_mut(offset)
+    }
+
+    ///Replace the element at `index`, returning the old one.
+    pub fn set(&mut self, index: u32, payload: T) -> T {
+        match self.get_mut(index) {
+            Some(val) => core::mem::replace(val, payload),
+            None => panic!("[set] index out of bound"),
+        }
+    }
+
+    pub fn swap(&mut self, a: u32, b: u32) {
+        if a >= self.size || b >= self.size {
+            panic!("[swap] index out of bound");
+        }
+        let ((pos_a, offset_a), (pos_b, offset_b)) = (self.locate(a), self.locate(b));
+        if pos_a == pos_b {
+            return self.bucket_mut(pos_a).swap(offset_a, offset_b);
+        }
+        let val_a = self.bucket_mut(pos_a).remove(offset_a);
+        let val_b = core::mem::replace(&mut self.bucket_mut(pos_b)[offset_b], val_a);
+        self.bucket_mut(pos_a).insert(offset_a, val_b);
+    }
+
+    ///Shorten the list to `len` elements, the dropped buckets are deleted without being read.
+    pub fn truncate(&mut self, len: u32) {
+        if len >= self.size {
+            return;
+        }
+        let (pos, offset) = self.locate(len);
+        //the bucket holding the new tail is kept, the buckets after it are dropped.
+        let keep = if offset > 0 { pos + 1 } else { pos };
+        for (id, count) in self.index_size.drain(keep..) {
+            self.cache.remove(&id);
+            self.removed.push(id);
+            self.size -= count;
+        }
+        self.prefix_sum.truncate(keep);
+        self.header_dirty = true;
+        if offset > 0 {
+            let dropped = self.index_size[pos].1 as usize - offset;
+            self.bucket_mut(pos).truncate(offset);
+            self.resize_bucket(pos, -(dropped as i32));
+            if offset < MERGE_SIZE as usize {
+                self.merge(pos);
+            }
+        }
+    }
+
+    ///Keep only the elements for which `f` returns true.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        for pos in 0..self.index_size.len() {
+            let bucket = self.bucket(pos);
+            let count = bucket.items.len();
+            bucket.items.retain(&mut f);
+            let removed = count - bucket.items.len();
+            if removed > 0 {
+                bucket.dirty = true;
+                self.resize_bucket(pos, -(removed as i32));
+            }
+        }
+        let mut pos = 0;
+        while pos < self.index_size.len() {
+            if self.index_size[pos].1 >= MERGE_SIZE || !self.merge(pos) {
+                pos += 1;
             }
-            self.cache.insert(bulk.0, temp);
         }
-        self.cache.get(&bulk.0).unwrap().get((index - start) as usize)
     }
 }
 
+impl<T> ListStore<T>
+where
+    for<'a> T: Decoder<'a> + Encoder + Clone,
+{
+    //elements of the bucket at `pos`, read from storage when not cached.
+    fn load(&self, pos: usize) -> Vec<T> {
+        let id = self.index_size[pos].0;
+        match self.cache.get(&id) {
+            Some(bucket) => bucket.items.clone(),
+            None => read_bucket(&self.key, id),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.range(..)
+    }
+
+    ///Iterate over the elements within `range`.
+    pub fn range<R: RangeBounds<u32>>(&self, range: R) -> Iter<'_, T> {
+        let front = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let back = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.size,
+        };
+        if front > back || back > self.size {
+            panic!("[range] index out of bound");
+        }
+        Iter {
+            list: self,
+            front,
+            back,
+            front_items: Vec::new().into_iter(),
+            back_items: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<T> Extend<T> for ListStore<T>
+where
+    for<'a> T: Decoder<'a> + Encoder,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for payload in iter {
+            self.push(payload);
+        }
+    }
+}
+
+fn write_bucket<T: Encoder>(key: &[u8], id: u32, items: &[T]) {
+    let mut sink = Sink::new(16);
+    sink.write_u32(items.len() as u32);
+    for item in items {
+        item.encode(&mut sink);
+    }
+    database::put(gen_key(key, id), sink.bytes());
+}
+
 impl<T: Encoder> ListStore<T> {
     pub fn flush(&mut self) {
-        if !self.need_flush.is_empty() {
-            let need_flush = self.need_flush.to_vec();
-            for k in need_flush {
-                let v = self.cache.get(&k).unwrap();
-                let l = v.len() as u32;
-                let mut sink = Sink::new(16);
-                sink.write_u32(l);
-                for i in v {
-                    i.encode(&mut sink);
-                }
-                let key: Vec<u8> = gen_key(self.key.as_slice(), k);
-                database::put(key.as_slice(), sink.bytes());
-            }
+        for id in self.removed.drain(..) {
+            database::delete(gen_key(&self.key, id));
+        }
+        for (&id, bucket) in self.cache.iter_mut().filter(|(_, bucket)| bucket.dirty) {
+            write_bucket(&self.key, id, &bucket.items);
+            bucket.dirty = false;
+        }
+        if self.header_dirty {
             let mut sink = Sink::new(16);
             self.encode(&mut sink);
-            database::put(&self.key, sink.bytes())
+            database::put(&self.key, sink.bytes());
+            self.header_dirty = false;
         }
     }
 }
 
-pub struct Iterator<'a, T>
-where
-    for<'b> T: Decoder<'b> + Encoder + 'static,
-{
-    cursor: u32,
-    list: &'a mut ListStore<T>,
+///Iterator over the elements of a `ListStore`, created by `ListStore::iter` and
+///`ListStore::range`.
+///
+///Elements are read bucket by bucket, the buckets not in cache are read from storage without
+///being cached.
+pub struct Iter<'a, T: Encoder> {
+    list: &'a ListStore<T>,
+    front: u32,
+    back: u32,
+    front_items: vec::IntoIter<T>,
+    back_items: vec::IntoIter<T>,
 }
 
-impl<'a, T> Iterator<'a, T>
+impl<'a, T> core::iter::Iterator for Iter<'a, T>
 where
-    for<'b> T: Decoder<'b> + Encoder,
+    for<'b> T: Decoder<'b> + Encoder + Clone,
 {
-    fn new(cursor: u32, list: &mut ListStore<T>) -> Iterator<T> {
-        Iterator { cursor, list }
-    }
+    type Item = T;
 
-    pub fn has_next(&self) -> bool {
-        if self.cursor >= self.list.size {
-            return false;
+    fn next(&mut self) -> Option<T> {
+        if self.front >= self.back {
+            return None;
         }
-        true
+        if self.front_items.len() == 0 {
+            let (pos, offset) = self.list.locate(self.front);
+            let mut items = self.list.load(pos);
+            self.front_items = items.split_off(offset).into_iter();
+        }
+        self.front += 1;
+        self.front_items.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.back - self.front) as usize;
+        (len, Some(len))
     }
+}
 
-    pub fn next(&mut self) -> Option<&T> {
-        if !self.has_next() {
+impl<'a, T> DoubleEndedIterator for Iter<'a, T>
+where
+    for<'b> T: Decoder<'b> + Encoder + Clone,
+{
+    fn next_back(&mut self) -> Option<T> {
+        if self.front >= self.back {
             return None;
         }
-        let temp = self.list.get(self.cursor);
-        self.cursor += 1;
-        temp
+        if self.back_items.len() == 0 {
+            let (pos, offset) = self.list.locate(self.back - 1);
+            let mut items = self.list.load(pos);
+            items.truncate(offset + 1);
+            self.back_items = items.into_iter();
+        }
+        self.back -= 1;
+        self.back_items.next_back()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> where for<'b> T: Decoder<'b> + Encoder + Clone {}
+
+impl<'a, T> IntoIterator for &'a ListStore<T>
+where
+    for<'b> T: Decoder<'b> + Encoder + Clone,
+{
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
     }
 }
 
@@ -376,7 +633,7 @@ fn list_node() {
 
     list2.remove(1);
     assert_eq!(list2.size, 1);
-    assert_eq!(list2.need_flush.len(), 1);
+    assert_eq!(list2.cache.values().filter(|bucket| bucket.dirty).count(), 1);
     list2.flush();
 
     let list3: ListStore<String> = ListStore::open(b"key");
@@ -389,20 +646,28 @@ fn test_iter() {
     for x in 0..90 {
         list.push(format!("hello{}", x));
     }
-    let iter = &mut list.iter();
     let mut i = 0;
-    loop {
-        if iter.has_next() {
-            if let Some(data) = iter.next() {
-                assert_eq!(format!("hello{}", i).as_str(), data);
-            } else {
-                assert_eq!(i, 90);
-            }
-            i += 1;
-        } else {
-            break;
-        }
+    for data in &list {
+        assert_eq!(format!("hello{}", i), data);
+        i += 1;
     }
+    assert_eq!(i, 90);
+    list.flush();
+
+    let list: ListStore<String> = ListStore::open(b"key");
+    let expect: Vec<String> = (0..90).map(|x| format!("hello{}", x)).collect();
+    assert_eq!(
+        list.iter().rev().collect::<Vec<_>>(),
+        expect.iter().rev().cloned().collect::<Vec<_>>()
+    );
+    assert_eq!(list.range(60..70).collect::<Vec<_>>(), expect[60..70]);
+    assert_eq!(list.range(..=0).collect::<Vec<_>>(), expect[..=0]);
+    let mut iter = list.range(10..80);
+    assert_eq!(iter.len(), 70);
+    assert_eq!(iter.next_back(), Some("hello79".to_string()));
+    assert_eq!(iter.next(), Some("hello10".to_string()));
+    assert_eq!(iter.len(), 68);
+    assert_eq!(iter.rev().step_by(10).map(|s| s.len()).sum::<usize>(), 7 * 7);
 }
 
 #[test]
@@ -416,6 +681,24 @@ fn clear() {
     list.clear();
     assert_eq!(list.size, 0);
 }
+#[test]
+fn test_clear_reopen() {
+    crate::mock::build_runtime();
+    let mut list: ListStore<u32> = ListStore::open(b"key");
+    list.extend(0..20 * INDEX_SIZE);
+    list.flush();
+
+    let mut list: ListStore<u32> = ListStore::open(b"key");
+    list.clear();
+    list.extend(0..12 * INDEX_SIZE);
+    list.flush();
+
+    let list: ListStore<u32> = ListStore::open(b"key");
+    assert_eq!(list.iter().collect::<Vec<_>>(), (0..12 * INDEX_SIZE).collect::<Vec<_>>());
+    let buckets = crate::runtime::storage_scan(b"key", b"", u32::MAX);
+    assert_eq!(buckets.len(), 12 + 1);
+}
+
 #[test]
 fn mock_test() {
     for _n in 0..1000 {
@@ -451,15 +734,88 @@ fn mock_test() {
                     }
                 }
                 181..200 => {
-                    let mut temp = Vec::new();
-                    let mut iter = list.iter();
-                    while let Some(val) = iter.next() {
-                        temp.push(*val);
-                    }
-                    assert_eq!(temp, array);
+                    assert_eq!(list.iter().collect::<Vec<_>>(), array);
                 }
                 _ => (),
             }
         }
     }
 }
+
+#[test]
+fn test_balance() {
+    crate::mock::build_runtime();
+    let mut list: ListStore<u32> = ListStore::open(b"key");
+    let mut array = Vec::new();
+    for i in 0..2000 {
+        let pos = rand::random::<u32>() % (array.len() as u32 + 1);
+        list.insert(pos, i);
+        array.insert(pos as usize, i);
+    }
+    assert!(list.cache.len() <= CACHE_SIZE);
+    assert!(list.index_size.iter().all(|&(_, count)| count <= INDEX_SIZE));
+    list.flush();
+
+    let mut list: ListStore<u32> = ListStore::open(b"key");
+    for _ in 0..1900 {
+        let pos = rand::random::<u32>() % list.len();
+        assert_eq!(list.remove(pos), array.remove(pos as usize));
+    }
+    // sparse buckets are merged with their neighbours.
+    assert!(list.index_size.len() <= 100 / MERGE_SIZE as usize + 1);
+    assert!(list.index_size.iter().all(|&(_, count)| count > 0));
+    drop(list);
+
+    let mut list: ListStore<u32> = ListStore::open(b"key");
+    assert_eq!(list.len(), 100);
+    assert!(list.contains(&array[50]));
+    for (i, val) in array.iter().enumerate() {
+        assert_eq!(list.get(i as u32), Some(val));
+    }
+    // the removed buckets are deleted from storage.
+    let buckets = crate::runtime::storage_scan(b"key", b"", u32::MAX);
+    assert_eq!(buckets.len(), list.index_size.len() + 1);
+}
+
+#[test]
+fn test_mutation() {
+    crate::mock::build_runtime();
+    let mut list: ListStore<u32> = ListStore::open(b"key");
+    list.extend(0..300);
+    list.flush();
+
+    let mut list: ListStore<u32> = ListStore::open(b"key");
+    *list.get_mut(5).unwrap() += 1000;
+    assert_eq!(list.set(200, 7), 200);
+    list.swap(0, 299);
+    list.swap(1, 2);
+    assert!(list.get_mut(300).is_none());
+    drop(list);
+
+    let mut list: ListStore<u32> = ListStore::open(b"key");
+    let mut array: Vec<u32> = (0..300).collect();
+    array[5] += 1000;
+    array[200] = 7;
+    array.swap(0, 299);
+    array.swap(1, 2);
+    assert_eq!(list.iter().collect::<Vec<_>>(), array);
+
+    list.retain(|val| val % 3 != 0);
+    array.retain(|val| val % 3 != 0);
+    assert_eq!(list.iter().collect::<Vec<_>>(), array);
+    list.retain(|val| !(10..=280).contains(val));
+    array.retain(|val| !(10..=280).contains(val));
+    assert_eq!(list.iter().collect::<Vec<_>>(), array);
+    assert_eq!(list.index_size.len(), 1);
+
+    list.extend(1000..1200);
+    array.extend(1000..1200);
+    list.truncate(150);
+    array.truncate(150);
+    list.truncate(160);
+    drop(list);
+    let list: ListStore<u32> = ListStore::open(b"key");
+    assert_eq!(list.iter().collect::<Vec<_>>(), array);
+    let buckets = crate::runtime::storage_scan(b"key", b"", u32::MAX);
+    assert_eq!(buckets.len(), list.index_size.len() + 1);
+}
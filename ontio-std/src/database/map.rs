@@ -0,0 +1,180 @@
+use crate::abi::{Decoder, Encoder, Sink};
+use crate::database;
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+use core::marker::PhantomData;
+
+struct Slot<V> {
+    value: Option<V>,
+    dirty: bool,
+}
+
+///Persistent map under a namespace prefix, with a write-back cache flushed on drop.
+///
+///The storage key of an entry is the length prefixed namespace followed by the encoded key, so the
+///entries of different namespaces never collide.
+/// # Example
+/// ```no_run
+/// # use ontio_std::database::MapStore;
+/// # use ontio_std::types::{Address, U128};
+/// # let owner = Address::repeat_byte(1);
+/// let mut balances: MapStore<Address, U128> = MapStore::open(b"balance");
+/// *balances.entry(&owner).or_default() += 100;
+/// assert_eq!(balances.get(&owner), Some(&U128::new(100)));
+/// ```
+pub struct MapStore<K: Encoder, V: Encoder> {
+    prefix: Vec<u8>,
+    cache: BTreeMap<Vec<u8>, Slot<V>>,
+    _phantom: PhantomData<fn(&K)>,
+}
+
+impl<K: Encoder, V: Encoder> Drop for MapStore<K, V> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl<K: Encoder, V: Encoder> MapStore<K, V> {
+    pub fn open(prefix: &[u8]) -> MapStore<K, V> {
+        MapStore { prefix: prefix.to_vec(), cache: BTreeMap::new(), _phantom: PhantomData }
+    }
+
+    fn storage_key(&self, key: &K) -> Vec<u8> {
+        let mut sink = Sink::new(self.prefix.len() + 24);
+        sink.write(self.prefix.as_slice());
+        sink.write(key);
+        sink.into()
+    }
+
+    pub fn insert(&mut self, key: &K, value: V) {
+        let key = self.storage_key(key);
+        self.cache.insert(key, Slot { value: Some(value), dirty: true });
+    }
+
+    pub fn remove(&mut self, key: &K) {
+        let key = self.storage_key(key);
+        self.cache.insert(key, Slot { value: None, dirty: true });
+    }
+
+    ///Write the modified entries to storage.
+    pub fn flush(&mut self) {
+        for (key, slot) in self.cache.iter_mut().filter(|(_, slot)| slot.dirty) {
+            match slot.value {
+                Some(ref value) => database::put(key, value),
+                None => database::delete(key),
+            }
+            slot.dirty = false;
+        }
+    }
+}
+
+impl<K: Encoder, V> MapStore<K, V>
+where
+    for<'a> V: Decoder<'a> + Encoder + 'static,
+{
+    fn slot(&mut self, key: &K) -> &mut Slot<V> {
+        let key = self.storage_key(key);
+        self.cache.entry(key).or_insert_with_key(|key| {
+            let value = database::get(key);
+            Slot { value, dirty: false }
+        })
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.slot(key).value.as_ref()
+    }
+
+    ///Mutable access to the value, which is written back on flush.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let slot = self.slot(key);
+        if slot.value.is_some() {
+            slot.dirty = true;
+        }
+        slot.value.as_mut()
+    }
+
+    pub fn contains_key(&mut self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    ///Entry of `key` for in-place update.
+    pub fn entry(&mut self, key: &K) -> Entry<'_, V> {
+        Entry { slot: self.slot(key) }
+    }
+}
+
+///Entry of a `MapStore`, created by `MapStore::entry`.
+pub struct Entry<'a, V> {
+    slot: &'a mut Slot<V>,
+}
+
+impl<'a, V> Entry<'a, V> {
+    pub fn get(&self) -> Option<&V> {
+        self.slot.value.as_ref()
+    }
+
+    ///Modify the value if present.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        if let Some(ref mut value) = self.slot.value {
+            f(value);
+            self.slot.dirty = true;
+        }
+        self
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        self.slot.dirty = true;
+        self.slot.value.get_or_insert_with(default)
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn remove(self) -> Option<V> {
+        self.slot.dirty = true;
+        self.slot.value.take()
+    }
+}
+
+#[test]
+fn test_map_store() {
+    use crate::types::{Address, U128};
+    crate::mock::build_runtime();
+    let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    {
+        let mut balances: MapStore<Address, U128> = MapStore::open(b"balance");
+        balances.insert(&alice, U128::new(10));
+        *balances.entry(&bob).or_default() += 5;
+        balances.entry(&alice).and_modify(|val| *val -= 3);
+        assert_eq!(balances.get(&alice), Some(&U128::new(7)));
+        assert!(!balances.contains_key(&Address::repeat_byte(3)));
+        // nothing is written before flush.
+        assert!(crate::runtime::storage_scan(b"", b"", 1).is_empty());
+    }
+    let mut balances: MapStore<Address, U128> = MapStore::open(b"balance");
+    assert_eq!(balances.get(&alice), Some(&U128::new(7)));
+    assert_eq!(balances.get(&bob), Some(&U128::new(5)));
+    balances.remove(&bob);
+    *balances.get_mut(&alice).unwrap() += 1;
+    balances.flush();
+
+    let mut balances: MapStore<Address, U128> = MapStore::open(b"balance");
+    assert!(!balances.contains_key(&bob));
+    assert_eq!(balances.entry(&alice).remove(), Some(U128::new(8)));
+    drop(balances);
+
+    // namespaces never collide.
+    let mut a: MapStore<&str, u32> = MapStore::open(b"ab");
+    a.insert(&"c", 1);
+    a.flush();
+    let mut b: MapStore<&str, u32> = MapStore::open(b"a");
+    assert!(!b.contains_key(&"bc"));
+}
@@ -0,0 +1,389 @@
+use crate::abi::{Decoder, Encoder, Error, Sink, Source};
+use crate::prelude::*;
+use crate::runtime;
+use crate::types::H256;
+
+const TYPE_LEAF: u8 = 0;
+const TYPE_INTERNAL: u8 = 1;
+
+///Hash of an empty subtree.
+pub const EMPTY_ROOT: H256 = H256([0; 32]);
+
+//a subtree holding a single leaf is stored as the leaf at the root of the subtree, so the nodes
+//along a path are only as many as needed to separate its leaf from the others.
+enum Node {
+    Leaf { path: H256, value: Vec<u8> },
+    Internal { left: H256, right: H256 },
+}
+
+impl Encoder for Node {
+    fn encode(&self, sink: &mut Sink) {
+        match self {
+            Node::Leaf { path, value } => {
+                sink.write(TYPE_LEAF);
+                sink.write(path);
+                sink.write(value.as_slice());
+            }
+            Node::Internal { left, right } => {
+                sink.write(TYPE_INTERNAL);
+                sink.write(left);
+                sink.write(right);
+            }
+        }
+    }
+}
+
+impl<'a> Decoder<'a> for Node {
+    fn decode(source: &mut Source<'a>) -> Result<Self, Error> {
+        match source.read_byte()? {
+            TYPE_LEAF => {
+                let path = source.read()?;
+                let value: &[u8] = source.read()?;
+                Ok(Node::Leaf { path, value: value.to_vec() })
+            }
+            TYPE_INTERNAL => Ok(Node::Internal { left: source.read()?, right: source.read()? }),
+            _ => Err(Error::IrregularData),
+        }
+    }
+}
+
+fn sha256(data: &[u8]) -> H256 {
+    runtime::sha256(data)
+}
+
+//bit of `path` selecting the child at `depth`, 0 for the left child.
+fn bit(path: &H256, depth: usize) -> u8 {
+    (path[depth / 8] >> (7 - depth % 8)) & 1
+}
+
+fn leaf_hash<F: Fn(&[u8]) -> H256>(path: &H256, value_hash: &H256, sha256: &F) -> H256 {
+    sha256(&[&[TYPE_LEAF], path.as_bytes(), value_hash.as_bytes()].concat())
+}
+
+fn node_hash<F: Fn(&[u8]) -> H256>(left: &H256, right: &H256, sha256: &F) -> H256 {
+    sha256(&[&[TYPE_INTERNAL], left.as_bytes(), right.as_bytes()].concat())
+}
+
+//internal node at `depth` along `path`, with `child` on the path.
+fn internal(path: &H256, depth: usize, child: H256, sibling: H256) -> Node {
+    match bit(path, depth) {
+        0 => Node::Internal { left: child, right: sibling },
+        _ => Node::Internal { left: sibling, right: child },
+    }
+}
+
+//content of a subtree after a removal.
+enum Subtree {
+    Empty,
+    Leaf(H256, Vec<u8>),
+    Internal(H256),
+}
+
+///Persistent sparse Merkle tree committing a map of byte keys to byte values.
+///
+///A key is placed at the path given by its sha256 hash, and the root hash commits to all the
+///entries, independently of the order they were inserted in. `prove` returns the proof of the
+///inclusion or the exclusion of a key, which `MerkleProof::verify` checks against a root.
+/// # Example
+/// ```no_run
+/// # use ontio_std::database::SparseMerkleTree;
+/// let tree = SparseMerkleTree::open(b"deposit");
+/// tree.insert(b"deposit_1", b"100");
+/// let root = tree.root();
+/// let proof = tree.prove(b"deposit_1");
+/// assert!(proof.verify(&root, b"deposit_1", Some(b"100")));
+/// assert!(!proof.verify(&root, b"deposit_1", None));
+/// ```
+pub struct SparseMerkleTree {
+    prefix: Vec<u8>,
+}
+
+impl SparseMerkleTree {
+    pub fn open(prefix: &[u8]) -> SparseMerkleTree {
+        SparseMerkleTree { prefix: prefix.to_vec() }
+    }
+
+    //storage key of the node at `depth` along `path`.
+    fn node_key(&self, depth: usize, path: &H256) -> Vec<u8> {
+        let mut key = self.prefix.clone();
+        key.extend_from_slice(&(depth as u16).to_le_bytes());
+        key.extend_from_slice(&path[..depth.div_ceil(8)]);
+        if !depth.is_multiple_of(8) {
+            *key.last_mut().unwrap() &= 0xff << (8 - depth % 8);
+        }
+        key
+    }
+
+    fn read(&self, depth: usize, path: &H256) -> Option<Node> {
+        let data = runtime::storage_read(&self.node_key(depth, path))?;
+        Some(Source::new(&data).read().unwrap())
+    }
+
+    fn write(&self, depth: usize, path: &H256, node: &Node) {
+        let mut sink = Sink::new(80);
+        sink.write(node);
+        runtime::storage_write(&self.node_key(depth, path), sink.bytes());
+    }
+
+    fn delete(&self, depth: usize, path: &H256) {
+        runtime::storage_delete(&self.node_key(depth, path));
+    }
+
+    fn hash(node: &Node) -> H256 {
+        match node {
+            Node::Leaf { path, value } => leaf_hash(path, &sha256(value), &sha256),
+            Node::Internal { left, right } => node_hash(left, right, &sha256),
+        }
+    }
+
+    pub fn root(&self) -> H256 {
+        self.read(0, &EMPTY_ROOT).map_or(EMPTY_ROOT, |node| Self::hash(&node))
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        let path = runtime::sha256(key);
+        for depth in 0..=256 {
+            match self.read(depth, &path)? {
+                Node::Leaf { path: leaf, value } => return (leaf == path).then_some(value),
+                Node::Internal { .. } => continue,
+            }
+        }
+        None
+    }
+
+    pub fn insert(&self, key: &[u8], value: &[u8]) {
+        let path = runtime::sha256(key);
+        self.insert_at(0, path, value.to_vec());
+    }
+
+    //insert the leaf into the subtree at `depth`, returning the new hash of the subtree.
+    fn insert_at(&self, depth: usize, path: H256, value: Vec<u8>) -> H256 {
+        let (left, right) = match self.read(depth, &path) {
+            Some(Node::Internal { left, right }) => (left, right),
+            Some(Node::Leaf { path: other, value: other_value }) if other != path => {
+                //push both leaves down to the first bit their paths differ.
+                let split = (depth..256).find(|&d| bit(&path, d) != bit(&other, d)).unwrap();
+                let other_leaf = Node::Leaf { path: other, value: other_value };
+                self.write(split + 1, &other, &other_leaf);
+                let leaf = Node::Leaf { path, value };
+                self.write(split + 1, &path, &leaf);
+                let mut hash = Self::hash(&leaf);
+                for d in (depth..=split).rev() {
+                    let sibling = if d == split { Self::hash(&other_leaf) } else { EMPTY_ROOT };
+                    let node = internal(&path, d, hash, sibling);
+                    self.write(d, &path, &node);
+                    hash = Self::hash(&node);
+                }
+                return hash;
+            }
+            _ => {
+                let node = Node::Leaf { path, value };
+                self.write(depth, &path, &node);
+                return Self::hash(&node);
+            }
+        };
+        let child = self.insert_at(depth + 1, path, value);
+        let node = internal(&path, depth, child, if bit(&path, depth) == 0 { right } else { left });
+        self.write(depth, &path, &node);
+        Self::hash(&node)
+    }
+
+    ///Remove `key`, returning false if absent.
+    pub fn remove(&self, key: &[u8]) -> bool {
+        let path = runtime::sha256(key);
+        match self.remove_at(0, &path) {
+            None => false,
+            Some(Subtree::Leaf(path, value)) => {
+                self.write(0, &path, &Node::Leaf { path, value });
+                true
+            }
+            Some(_) => true,
+        }
+    }
+
+    //remove the leaf from the subtree at `depth`, returning the content left in the subtree. A
+    //single leaf left is returned to be written by the caller at the root of the subtree.
+    fn remove_at(&self, depth: usize, path: &H256) -> Option<Subtree> {
+        let (left, right) = match self.read(depth, path)? {
+            Node::Leaf { path: leaf, .. } if leaf == *path => {
+                self.delete(depth, path);
+                return Some(Subtree::Empty);
+            }
+            Node::Leaf { .. } => return None,
+            Node::Internal { left, right } => (left, right),
+        };
+        let child = self.remove_at(depth + 1, path)?;
+        let mut sibling_path = *path;
+        sibling_path.as_mut()[depth / 8] ^= 0x80 >> (depth % 8);
+        let sibling = if bit(path, depth) == 0 { right } else { left };
+        let child_hash = match child {
+            Subtree::Internal(hash) => hash,
+            Subtree::Leaf(leaf, value) if sibling == EMPTY_ROOT => {
+                self.delete(depth, path);
+                return Some(Subtree::Leaf(leaf, value));
+            }
+            Subtree::Leaf(leaf, value) => {
+                let node = Node::Leaf { path: leaf, value };
+                self.write(depth + 1, path, &node);
+                Self::hash(&node)
+            }
+            Subtree::Empty => match self.read(depth + 1, &sibling_path) {
+                Some(Node::Leaf { path: leaf, value }) => {
+                    self.delete(depth + 1, &sibling_path);
+                    self.delete(depth, path);
+                    return Some(Subtree::Leaf(leaf, value));
+                }
+                _ => EMPTY_ROOT,
+            },
+        };
+        let node = internal(path, depth, child_hash, sibling);
+        self.write(depth, path, &node);
+        Some(Subtree::Internal(Self::hash(&node)))
+    }
+
+    ///Proof of the inclusion or the exclusion of `key`.
+    pub fn prove(&self, key: &[u8]) -> MerkleProof {
+        let path = runtime::sha256(key);
+        let mut siblings = Vec::new();
+        let mut leaf = None;
+        for depth in 0..=256 {
+            match self.read(depth, &path) {
+                Some(Node::Internal { left, right }) => {
+                    siblings.push(if bit(&path, depth) == 0 { right } else { left });
+                }
+                Some(Node::Leaf { path, value }) => {
+                    leaf = Some((path, runtime::sha256(value)));
+                    break;
+                }
+                None => break,
+            }
+        }
+        MerkleProof { siblings, leaf }
+    }
+}
+
+///Proof of the inclusion or the exclusion of a key in a `SparseMerkleTree`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleProof {
+    ///Siblings of the nodes along the path of the key, from the root down.
+    pub siblings: Vec<H256>,
+    ///Path and value hash of the leaf at the end of the path, if any.
+    pub leaf: Option<(H256, H256)>,
+}
+
+impl Encoder for MerkleProof {
+    fn encode(&self, sink: &mut Sink) {
+        sink.write(&self.siblings);
+        sink.write(self.leaf);
+    }
+}
+
+impl<'a> Decoder<'a> for MerkleProof {
+    fn decode(source: &mut Source<'a>) -> Result<Self, Error> {
+        Ok(MerkleProof { siblings: source.read()?, leaf: source.read()? })
+    }
+}
+
+impl MerkleProof {
+    ///Check the proof against `root`, proving that `key` maps to `value`, or is absent if `value`
+    ///is `None`.
+    pub fn verify(&self, root: &H256, key: &[u8], value: Option<&[u8]>) -> bool {
+        self.verify_with(root, key, value, sha256)
+    }
+
+    ///`verify` with the given sha256 implementation, for the verification out of a contract.
+    pub fn verify_with<F: Fn(&[u8]) -> H256>(
+        &self, root: &H256, key: &[u8], value: Option<&[u8]>, sha256: F,
+    ) -> bool {
+        let path = sha256(key);
+        let depth = self.siblings.len();
+        if depth > 256 {
+            return false;
+        }
+        let leaf_matched = match (value, &self.leaf) {
+            (Some(value), Some((leaf, value_hash))) => {
+                *leaf == path && *value_hash == sha256(value)
+            }
+            (Some(_), None) => false,
+            //another leaf ends the path of an absent key, sharing the path to that point.
+            (None, Some((leaf, _))) => {
+                *leaf != path && (0..depth).all(|d| bit(leaf, d) == bit(&path, d))
+            }
+            (None, None) => true,
+        };
+        if !leaf_matched {
+            return false;
+        }
+        let mut hash = match &self.leaf {
+            Some((leaf, value_hash)) => leaf_hash(leaf, value_hash, &sha256),
+            None => EMPTY_ROOT,
+        };
+        for (d, sibling) in self.siblings.iter().enumerate().rev() {
+            hash = match bit(&path, d) {
+                0 => node_hash(&hash, sibling, &sha256),
+                _ => node_hash(sibling, &hash, &sha256),
+            };
+        }
+        hash == *root
+    }
+}
+
+#[test]
+fn test_sparse_merkle_tree() {
+    use sha2::Digest;
+    crate::mock::build_runtime();
+    let keys: Vec<Vec<u8>> = (0..200u32).map(|i| format!("deposit_{}", i).into_bytes()).collect();
+    let tree = SparseMerkleTree::open(b"a");
+    assert_eq!(tree.root(), EMPTY_ROOT);
+    for key in keys.iter() {
+        tree.insert(key, key);
+    }
+    tree.insert(&keys[0], b"updated");
+
+    // the root does not depend on the insertion order.
+    let other = SparseMerkleTree::open(b"b");
+    other.insert(&keys[0], b"updated");
+    for key in keys.iter().skip(1).rev() {
+        other.insert(key, key);
+    }
+    let root = tree.root();
+    assert_eq!(other.root(), root);
+
+    let sha256 = |data: &[u8]| H256::from_slice(&sha2::Sha256::digest(data));
+    for key in keys.iter().skip(1).step_by(7) {
+        let proof = tree.prove(key);
+        assert!(proof.verify(&root, key, Some(key)));
+        assert!(proof.verify_with(&root, key, Some(key), sha256));
+        assert!(!proof.verify(&root, key, Some(b"forged")));
+        assert!(!proof.verify(&root, key, None));
+        assert_eq!(tree.get(key).as_deref(), Some(key.as_slice()));
+    }
+    assert_eq!(tree.get(&keys[0]), Some(b"updated".to_vec()));
+    let proof = tree.prove(b"absent");
+    assert_eq!(tree.get(b"absent"), None);
+    assert!(proof.verify(&root, b"absent", None));
+    assert!(!proof.verify(&root, b"absent", Some(b"")));
+
+    let mut sink = Sink::new(64);
+    sink.write(&proof);
+    assert_eq!(Source::new(sink.bytes()).read::<MerkleProof>().unwrap(), proof);
+
+    // removing entries gives the root of the tree built without them.
+    for key in keys.iter().skip(100) {
+        assert!(tree.remove(key));
+    }
+    assert!(!tree.remove(b"absent"));
+    let rebuilt = SparseMerkleTree::open(b"c");
+    rebuilt.insert(&keys[0], b"updated");
+    for key in keys.iter().take(100).skip(1) {
+        rebuilt.insert(key, key);
+    }
+    assert_eq!(tree.root(), rebuilt.root());
+    assert!(tree.prove(&keys[150]).verify(&tree.root(), &keys[150], None));
+
+    for key in keys.iter().take(100) {
+        assert!(tree.remove(key));
+    }
+    assert_eq!(tree.root(), EMPTY_ROOT);
+    assert!(runtime::storage_scan(b"a", b"", u32::MAX).is_empty());
+}
@@ -1,10 +1,21 @@
 mod list;
+mod map;
+mod merkle;
+mod schema;
+mod sorted_map;
+mod value;
 
-pub use self::list::ListStore;
+pub use self::list::{Iter, ListStore};
+pub use self::map::{Entry, MapStore};
+pub use self::merkle::{MerkleProof, SparseMerkleTree, EMPTY_ROOT};
+pub use self::schema::Schema;
+pub use self::sorted_map::{Range, SortedMapStore, SortedSetStore};
+pub use self::value::StorageValue;
 
 use super::abi::{Decoder, Encoder, Sink, Source};
 use super::prelude::*;
 use super::runtime;
+use core::marker::PhantomData;
 
 #[track_caller]
 pub fn get<K: AsRef<[u8]>, T>(key: K) -> Option<T>
@@ -25,3 +36,97 @@ pub fn put<K: AsRef<[u8]>, T: Encoder>(key: K, val: T) {
 pub fn delete<K: AsRef<[u8]>>(key: K) {
     runtime::storage_delete(key.as_ref());
 }
+
+/// Decoded key/value pairs whose key starts with `prefix`, in ascending key order, see
+/// `runtime::storage_scan` for the pagination by `start` and `limit`.
+#[track_caller]
+pub fn scan<P: AsRef<[u8]>, S: AsRef<[u8]>, T>(prefix: P, start: S, limit: u32) -> Vec<(Vec<u8>, T)>
+where
+    for<'a> T: Decoder<'a> + 'static,
+{
+    runtime::storage_scan(prefix.as_ref(), start.as_ref(), limit)
+        .into_iter()
+        .map(|(key, val)| (key, Source::new(&val).read().unwrap()))
+        .collect()
+}
+
+/// Iterate over the decoded key/value pairs whose key starts with `prefix`, in ascending key order.
+///
+/// The pairs are loaded from storage by pages of `PrefixIter::PAGE_SIZE`.
+/// # Example
+/// ```no_run
+/// # use ontio_std::database;
+/// for (key, amount) in database::iter_prefix::<_, u64>(b"balance_") {
+///     // ...
+/// }
+/// ```
+pub fn iter_prefix<P: AsRef<[u8]>, T>(prefix: P) -> PrefixIter<T>
+where
+    for<'a> T: Decoder<'a> + 'static,
+{
+    PrefixIter {
+        prefix: prefix.as_ref().to_vec(),
+        next: Some(Vec::new()),
+        page: Vec::new().into_iter(),
+        _phantom: PhantomData,
+    }
+}
+
+pub struct PrefixIter<T> {
+    prefix: Vec<u8>,
+    next: Option<Vec<u8>>,
+    page: vec::IntoIter<(Vec<u8>, Vec<u8>)>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> PrefixIter<T> {
+    pub const PAGE_SIZE: u32 = 32;
+}
+
+impl<T> Iterator for PrefixIter<T>
+where
+    for<'a> T: Decoder<'a> + 'static,
+{
+    type Item = (Vec<u8>, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, val)) = self.page.next() {
+                return Some((key, Source::new(&val).read().unwrap()));
+            }
+            let start = self.next.take()?;
+            let page = runtime::storage_scan(&self.prefix, &start, Self::PAGE_SIZE);
+            if page.len() == Self::PAGE_SIZE as usize {
+                // the smallest key after the last one.
+                let mut next = page[page.len() - 1].0.clone();
+                next.push(0);
+                self.next = Some(next);
+            }
+            if page.is_empty() {
+                return None;
+            }
+            self.page = page.into_iter();
+        }
+    }
+}
+
+#[test]
+fn test_scan() {
+    crate::mock::build_runtime();
+    for i in 0..100u64 {
+        put(format!("item_{:03}", i), i);
+    }
+    put("itemx", 0u64);
+    put("a", 0u64);
+
+    let page: Vec<(Vec<u8>, u64)> = scan("item_", "item_050", 3);
+    let keys: Vec<&[u8]> = page.iter().map(|(key, _)| key.as_slice()).collect();
+    assert_eq!(keys, [b"item_050", b"item_051", b"item_052"]);
+    assert_eq!(page[2].1, 52);
+    assert_eq!(scan::<_, _, u64>("item_", "", 200).len(), 100);
+    assert!(scan::<_, _, u64>("item_", "item_100", 10).is_empty());
+
+    let values: Vec<u64> = iter_prefix("item_").map(|(_, val)| val).collect();
+    assert_eq!(values, (0..100).collect::<Vec<_>>());
+    assert_eq!(iter_prefix::<_, u64>("none").count(), 0);
+}
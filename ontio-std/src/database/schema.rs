@@ -0,0 +1,236 @@
+use crate::abi::{Decoder, Encoder, Error, Sink, Source};
+use crate::prelude::*;
+use crate::runtime;
+use alloc::collections::BTreeMap;
+use core::marker::PhantomData;
+
+type Migration = dyn Fn(&[u8]) -> Result<Vec<u8>, Error>;
+
+//header of the versioned records, followed by the version byte. A length prefixed value never
+//starts with 0xff, which tells the versioned records from the legacy ones.
+const MAGIC: [u8; 4] = *b"\xffver";
+
+//version of the records written before adopting `Schema`, which have no header.
+const LEGACY: u8 = 0;
+
+///Versioned storage format of `T` with the migrations from its older formats.
+///
+///Every record is stored as a header with its version followed by the encoded value. Records of an
+///older version are upgraded by chaining the registered migrations, either lazily when read by
+///`get`, or in bounded batches by `migrate_batch`.
+///
+///The records written before adopting `Schema` have no header, they are upgraded by the migration
+///registered with `legacy`. Such a record starting with the bytes `ff 76 65 72` would be taken as
+///versioned, the legacy format must not produce them.
+/// # Example
+/// ```no_run
+/// # use ontio_std::abi::{Decoder, Encoder};
+/// # use ontio_std::database::Schema;
+/// # use ontio_std::types::U128;
+/// #[derive(Encoder, Decoder)]
+/// struct AccountV1 {
+///     balance: u64,
+/// }
+///
+/// #[derive(Encoder, Decoder)]
+/// struct Account {
+///     balance: U128,
+///     frozen: bool,
+/// }
+///
+/// let schema = Schema::<Account>::new(2)
+///     .legacy(|balance: u64| AccountV1 { balance })
+///     .migration(1, |old: AccountV1| Account {
+///         balance: U128::new(old.balance as u128),
+///         frozen: false,
+///     });
+/// let account = schema.get(b"account_alice").expect("invalid account");
+/// ```
+pub struct Schema<T> {
+    version: u8,
+    migrations: BTreeMap<u8, Box<Migration>>, //from version, migration to the next version
+    _phantom: PhantomData<fn(&T) -> T>,
+}
+
+impl<T> Schema<T>
+where
+    for<'a> T: Decoder<'a> + Encoder + 'static,
+{
+    ///Schema whose current format is `version`, starting from 1.
+    pub fn new(version: u8) -> Self {
+        assert!(version > LEGACY, "version 0 is the legacy format");
+        Schema { version, migrations: BTreeMap::new(), _phantom: PhantomData }
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    ///Register the migration of the records from version `from` to `from + 1`.
+    pub fn migration<Old, New, F>(mut self, from: u8, migrate: F) -> Self
+    where
+        for<'a> Old: Decoder<'a> + 'static,
+        New: Encoder,
+        F: Fn(Old) -> New + 'static,
+    {
+        assert!(from < self.version, "migration from the current version");
+        let migration = move |data: &[u8]| {
+            let old: Old = Source::new(data).read()?;
+            let mut sink = Sink::new(data.len());
+            sink.write(migrate(old));
+            Ok(sink.into())
+        };
+        self.migrations.insert(from, Box::new(migration));
+        self
+    }
+
+    ///Register the migration of the records written without version, before adopting `Schema`,
+    ///to version 1.
+    pub fn legacy<Old, New, F>(self, migrate: F) -> Self
+    where
+        for<'a> Old: Decoder<'a> + 'static,
+        New: Encoder,
+        F: Fn(Old) -> New + 'static,
+    {
+        self.migration(LEGACY, migrate)
+    }
+
+    fn encode(&self, val: &T) -> Vec<u8> {
+        let mut sink = Sink::new(16);
+        sink.write_bytes(&MAGIC);
+        sink.write(self.version);
+        sink.write(val);
+        sink.into()
+    }
+
+    //version and data of a record.
+    fn split(record: &[u8]) -> (u8, &[u8]) {
+        match record.strip_prefix(&MAGIC[..]).and_then(|rest| rest.split_first()) {
+            Some((&version, data)) => (version, data),
+            None => (LEGACY, record),
+        }
+    }
+
+    ///Decode a record, upgrading it to the current version.
+    pub fn decode(&self, record: &[u8]) -> Result<T, Error> {
+        let (version, data) = Self::split(record);
+        if version > self.version {
+            return Err(Error::IrregularData);
+        }
+        let mut data = data.to_vec();
+        for from in version..self.version {
+            let migration = self.migrations.get(&from).ok_or(Error::IrregularData)?;
+            data = migration(&data)?;
+        }
+        Source::new(&data).read()
+    }
+
+    ///Version of the record at `key`, 0 for a legacy record.
+    pub fn version_of<K: AsRef<[u8]>>(&self, key: K) -> Option<u8> {
+        runtime::storage_read(key.as_ref()).map(|record| Self::split(&record).0)
+    }
+
+    pub fn put<K: AsRef<[u8]>>(&self, key: K, val: &T) {
+        runtime::storage_write(key.as_ref(), &self.encode(val));
+    }
+
+    ///Read the record at `key`, a record of an older version is upgraded and written back.
+    ///
+    ///Returns `Ok(None)` if there is no record, and `Err` if the record can not be decoded.
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Result<Option<T>, Error> {
+        let record = match runtime::storage_read(key.as_ref()) {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let val = self.decode(&record)?;
+        if Self::split(&record).0 < self.version {
+            self.put(key, &val);
+        }
+        Ok(Some(val))
+    }
+
+    ///Upgrade at most `limit` records whose key starts with `prefix`, from `start` on in ascending
+    ///key order. Returns the number of records upgraded and the start of the next batch, `None`
+    ///when all the records have been visited.
+    ///
+    ///Stops with `Err` at the first record which can not be decoded, the records before it are
+    ///upgraded already.
+    pub fn migrate_batch<P: AsRef<[u8]>, S: AsRef<[u8]>>(
+        &self, prefix: P, start: S, limit: u32,
+    ) -> Result<(u32, Option<Vec<u8>>), Error> {
+        let page = runtime::storage_scan(prefix.as_ref(), start.as_ref(), limit);
+        let mut upgraded = 0;
+        for (key, record) in page.iter() {
+            if Self::split(record).0 < self.version {
+                let val = self.decode(record)?;
+                self.put(key, &val);
+                upgraded += 1;
+            }
+        }
+        let next = match page.last() {
+            // the smallest key after the last one.
+            Some((key, _)) if page.len() == limit as usize => Some([key.as_slice(), &[0]].concat()),
+            _ => None,
+        };
+        Ok((upgraded, next))
+    }
+}
+
+#[test]
+fn test_migration() {
+    use crate::types::U128;
+    let handle = crate::mock::build_runtime();
+    let v1: Schema<u64> = Schema::new(1);
+    for i in 0..10u64 {
+        v1.put(format!("account_{}", i), &(i * 100));
+    }
+    let v2: Schema<(u64, bool)> = Schema::new(2).migration(1, |balance: u64| (balance, false));
+    v2.put("account_3", &(7, true));
+
+    let v3: Schema<(U128, bool)> = Schema::new(3)
+        .migration(1, |balance: u64| (balance, false))
+        .migration(2, |(balance, frozen): (u64, bool)| (U128::new(balance as u128), frozen));
+    assert_eq!(v3.get("account_1").unwrap(), Some((U128::new(100), false)));
+    assert_eq!(v3.version_of("account_1"), Some(3));
+    assert_eq!(v3.version_of("account_2"), Some(1));
+    assert_eq!(v3.get("account_3").unwrap(), Some((U128::new(7), true)));
+    assert_eq!(v3.get("account_x").unwrap(), None);
+
+    let (upgraded, next) = v3.migrate_batch("account_", "", 4).unwrap();
+    assert_eq!((upgraded, next.as_deref()), (2, Some(&b"account_3\0"[..])));
+    let (upgraded, next) = v3.migrate_batch("account_", next.unwrap(), 10).unwrap();
+    assert_eq!((upgraded, next), (6, None));
+    assert_eq!(v3.decode(&handle.storage_read(b"account_9").unwrap()).unwrap().0, U128::new(900));
+
+    // newer records and missing migrations are rejected.
+    assert!(v2.decode(&[3, 0]).is_err());
+    let v4: Schema<u8> = Schema::new(4).migration(3, |_: (U128, bool)| 0u8);
+    assert!(v4.decode(&v1.encode(&1)).is_err());
+}
+
+#[test]
+fn test_legacy() {
+    let handle = crate::mock::build_runtime();
+    for i in 0..4u64 {
+        crate::database::put(format!("balance_{}", i), i * 100);
+    }
+    crate::database::put("name", "alice");
+
+    // legacy records without migration are rejected instead of misread.
+    let v1: Schema<u64> = Schema::new(1);
+    assert_eq!(v1.version_of("balance_1"), Some(0));
+    assert!(matches!(v1.get("balance_1"), Err(Error::IrregularData)));
+    assert!(v1.migrate_batch("balance_", "", 10).is_err());
+
+    let v2: Schema<(u64, bool)> =
+        Schema::new(2).legacy(|balance: u64| balance).migration(1, |balance: u64| (balance, false));
+    v2.put("balance_3", &(7, true));
+    assert_eq!(v2.get("balance_1").unwrap(), Some((100, false)));
+    assert_eq!(v2.version_of("balance_1"), Some(2));
+    assert_eq!(v2.migrate_batch("balance_", "", 10).unwrap(), (2, None));
+    assert_eq!(v2.get("balance_3").unwrap(), Some((7, true)));
+    assert_eq!(v2.decode(&handle.storage_read(b"balance_2").unwrap()).unwrap(), (200, false));
+
+    let names: Schema<String> = Schema::new(1).legacy(|name: String| name);
+    assert_eq!(names.get("name").unwrap(), Some("alice".to_string()));
+}
@@ -0,0 +1,741 @@
+use super::list::gen_key;
+use crate::abi::{Decoder, Encoder, Error, Sink, Source};
+use crate::database;
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+use core::ops::{Bound, RangeBounds};
+
+//max number of entries of a leaf, and of children of an internal node.
+const NODE_SIZE: usize = 32;
+//a node smaller than this is merged with or refilled from a sibling.
+const MIN_SIZE: usize = NODE_SIZE / 2;
+
+const TYPE_LEAF: u8 = 0;
+const TYPE_INTERNAL: u8 = 1;
+
+//`keys[i]` of an internal node is the first key of `children[i + 1]`, and `counts[i]` the number
+//of entries under `children[i]`.
+#[derive(Clone)]
+enum Node<K, V> {
+    Leaf { keys: Vec<K>, values: Vec<V> },
+    Internal { keys: Vec<K>, children: Vec<u32>, counts: Vec<u32> },
+}
+
+impl<K: Encoder, V: Encoder> Encoder for Node<K, V> {
+    fn encode(&self, sink: &mut Sink) {
+        match self {
+            Node::Leaf { keys, values } => {
+                sink.write(TYPE_LEAF);
+                sink.write(keys);
+                sink.write(values);
+            }
+            Node::Internal { keys, children, counts } => {
+                sink.write(TYPE_INTERNAL);
+                sink.write(keys);
+                sink.write(children);
+                sink.write(counts);
+            }
+        }
+    }
+}
+
+impl<'a, K: Decoder<'a>, V: Decoder<'a>> Decoder<'a> for Node<K, V> {
+    fn decode(source: &mut Source<'a>) -> Result<Self, Error> {
+        match source.read_byte()? {
+            TYPE_LEAF => Ok(Node::Leaf { keys: source.read()?, values: source.read()? }),
+            TYPE_INTERNAL => Ok(Node::Internal {
+                keys: source.read()?,
+                children: source.read()?,
+                counts: source.read()?,
+            }),
+            _ => Err(Error::IrregularData),
+        }
+    }
+}
+
+impl<K: Ord + Clone, V> Node<K, V> {
+    //number of entries under the node.
+    fn len(&self) -> u32 {
+        match self {
+            Node::Leaf { keys, .. } => keys.len() as u32,
+            Node::Internal { counts, .. } => counts.iter().sum(),
+        }
+    }
+
+    //number of entries of a leaf, or children of an internal node.
+    fn size(&self) -> usize {
+        match self {
+            Node::Leaf { keys, .. } => keys.len(),
+            Node::Internal { children, .. } => children.len(),
+        }
+    }
+
+    //move the upper half to a new node, returning its first key.
+    fn split(&mut self) -> (K, Node<K, V>) {
+        let mid = self.size() / 2;
+        match self {
+            Node::Leaf { keys, values } => {
+                let right = Node::Leaf { keys: keys.split_off(mid), values: values.split_off(mid) };
+                (keys_of(&right)[0].clone(), right)
+            }
+            Node::Internal { keys, children, counts } => {
+                let right_keys = keys.split_off(mid);
+                let sep = keys.pop().unwrap();
+                let right = Node::Internal {
+                    keys: right_keys,
+                    children: children.split_off(mid),
+                    counts: counts.split_off(mid),
+                };
+                (sep, right)
+            }
+        }
+    }
+
+    //append the right sibling `other`, `sep` being its first key.
+    fn absorb(&mut self, sep: K, other: Node<K, V>) {
+        match (self, other) {
+            (Node::Leaf { keys, values }, Node::Leaf { keys: k, values: v }) => {
+                keys.extend(k);
+                values.extend(v);
+            }
+            (
+                Node::Internal { keys, children, counts },
+                Node::Internal { keys: k, children: c, counts: n },
+            ) => {
+                keys.push(sep);
+                keys.extend(k);
+                children.extend(c);
+                counts.extend(n);
+            }
+            _ => unreachable!("siblings of different height"),
+        }
+    }
+}
+
+fn keys_of<K, V>(node: &Node<K, V>) -> &[K] {
+    match node {
+        Node::Leaf { keys, .. } | Node::Internal { keys, .. } => keys,
+    }
+}
+
+//index of the child of an internal node which may contain `key`.
+fn child_index<K: Ord>(keys: &[K], key: &K) -> usize {
+    keys.partition_point(|sep| sep <= key)
+}
+
+struct Page<K, V> {
+    node: Node<K, V>,
+    dirty: bool,
+}
+
+///Persistent sorted map, stored as a B+ tree whose nodes are pages under a common prefix.
+///
+///Lookups and updates touch one page per level, and every internal node records the number of
+///entries of its subtrees, so rank queries are logarithmic as well. Modified pages are cached and
+///written back on flush or drop.
+/// # Example
+/// ```no_run
+/// # use ontio_std::database::SortedMapStore;
+/// # use ontio_std::types::Address;
+/// # let (alice, bob) = (Address::repeat_byte(1), Address::repeat_byte(2));
+/// let mut scores: SortedMapStore<(u64, Address), ()> = SortedMapStore::open(b"scores");
+/// scores.insert((30, alice), ());
+/// scores.insert((50, bob), ());
+/// assert_eq!(scores.last(), Some((&(50, bob), &())));
+/// assert_eq!(scores.rank(&(50, bob)), 1);
+/// ```
+pub struct SortedMapStore<K: Encoder, V: Encoder> {
+    prefix: Vec<u8>,
+    root: u32,
+    next_id: u32,
+    len: u32,
+    cache: BTreeMap<u32, Page<K, V>>,
+    removed: Vec<u32>, //id of the pages to delete on flush
+    header_dirty: bool,
+}
+
+impl<K: Encoder, V: Encoder> Drop for SortedMapStore<K, V> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl<K: Encoder, V: Encoder> SortedMapStore<K, V> {
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    ///Write the modified pages to storage.
+    pub fn flush(&mut self) {
+        for id in self.removed.drain(..) {
+            database::delete(gen_key(&self.prefix, id));
+        }
+        for (&id, page) in self.cache.iter_mut().filter(|(_, page)| page.dirty) {
+            database::put(gen_key(&self.prefix, id), &page.node);
+            page.dirty = false;
+        }
+        if self.header_dirty {
+            database::put(&self.prefix, (self.root, self.next_id, self.len));
+            self.header_dirty = false;
+        }
+    }
+}
+
+impl<K, V> SortedMapStore<K, V>
+where
+    for<'a> K: Decoder<'a> + Encoder + Ord + Clone + 'static,
+    for<'a> V: Decoder<'a> + Encoder + 'static,
+{
+    pub fn open(prefix: &[u8]) -> SortedMapStore<K, V> {
+        let mut map = SortedMapStore {
+            prefix: prefix.to_vec(),
+            root: 0,
+            next_id: 1,
+            len: 0,
+            cache: BTreeMap::new(),
+            removed: Vec::new(),
+            header_dirty: false,
+        };
+        match database::get::<_, (u32, u32, u32)>(prefix) {
+            Some((root, next_id, len)) => {
+                map.root = root;
+                map.next_id = next_id;
+                map.len = len;
+            }
+            //the empty root is only written once modified.
+            None => {
+                let node = Node::Leaf { keys: Vec::new(), values: Vec::new() };
+                map.cache.insert(0, Page { node, dirty: false });
+            }
+        }
+        map
+    }
+
+    fn node(&mut self, id: u32) -> &mut Node<K, V> {
+        let prefix = &self.prefix;
+        let page = self.cache.entry(id).or_insert_with(|| {
+            let node = database::get(gen_key(prefix, id)).unwrap();
+            Page { node, dirty: false }
+        });
+        &mut page.node
+    }
+
+    //the node at `id` to be modified.
+    fn node_mut(&mut self, id: u32) -> &mut Node<K, V> {
+        self.node(id);
+        let page = self.cache.get_mut(&id).unwrap();
+        page.dirty = true;
+        &mut page.node
+    }
+
+    fn new_node(&mut self, node: Node<K, V>) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.header_dirty = true;
+        self.cache.insert(id, Page { node, dirty: true });
+        id
+    }
+
+    fn remove_node(&mut self, id: u32) -> Node<K, V> {
+        self.node(id);
+        self.removed.push(id);
+        self.cache.remove(&id).unwrap().node
+    }
+
+    //the leaf which may contain `key`.
+    fn leaf(&mut self, key: &K) -> u32 {
+        let mut id = self.root;
+        while let Node::Internal { keys, children, .. } = self.node(id) {
+            id = children[child_index(keys, key)];
+        }
+        id
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let id = self.leaf(key);
+        match self.node(id) {
+            Node::Leaf { keys, values } => keys.binary_search(key).ok().map(move |i| &values[i]),
+            Node::Internal { .. } => unreachable!(),
+        }
+    }
+
+    ///Mutable access to the value, which is written back on flush.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let id = self.leaf(key);
+        let i = match self.node(id) {
+            Node::Leaf { keys, .. } => keys.binary_search(key).ok()?,
+            Node::Internal { .. } => unreachable!(),
+        };
+        match self.node_mut(id) {
+            Node::Leaf { values, .. } => Some(&mut values[i]),
+            Node::Internal { .. } => unreachable!(),
+        }
+    }
+
+    pub fn contains_key(&mut self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    ///Insert an entry, returning the old value of `key`.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let root = self.root;
+        let (old, split) = self.insert_at(root, key, value);
+        if let Some((sep, right)) = split {
+            let counts = vec![self.node(root).len(), self.node(right).len()];
+            let node = Node::Internal { keys: vec![sep], children: vec![root, right], counts };
+            self.root = self.new_node(node);
+        }
+        if old.is_none() {
+            self.len += 1;
+            self.header_dirty = true;
+        }
+        old
+    }
+
+    //insert into the subtree at `id`, returning the old value and the node split off if full.
+    fn insert_at(&mut self, id: u32, key: K, value: V) -> (Option<V>, Option<(K, u32)>) {
+        let (i, child) = match self.node_mut(id) {
+            Node::Leaf { keys, values } => match keys.binary_search(&key) {
+                Ok(i) => return (Some(core::mem::replace(&mut values[i], value)), None),
+                Err(i) => {
+                    keys.insert(i, key);
+                    values.insert(i, value);
+                    return (None, self.split_full(id));
+                }
+            },
+            Node::Internal { keys, children, .. } => {
+                let i = child_index(keys, &key);
+                (i, children[i])
+            }
+        };
+        let (old, split) = self.insert_at(child, key, value);
+        if old.is_some() {
+            return (old, None);
+        }
+        let split = split.map(|(sep, right)| (sep, right, self.node(right).len()));
+        let child_len = self.node(child).len();
+        if let Node::Internal { keys, children, counts } = self.node_mut(id) {
+            counts[i] = child_len;
+            if let Some((sep, right, right_len)) = split {
+                keys.insert(i, sep);
+                children.insert(i + 1, right);
+                counts.insert(i + 1, right_len);
+            }
+        }
+        (None, self.split_full(id))
+    }
+
+    fn split_full(&mut self, id: u32) -> Option<(K, u32)> {
+        let node = self.node_mut(id);
+        if node.size() <= NODE_SIZE {
+            return None;
+        }
+        let (sep, right) = node.split();
+        Some((sep, self.new_node(right)))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let root = self.root;
+        let old = self.remove_at(root, key)?;
+        self.len -= 1;
+        self.header_dirty = true;
+        if let Node::Internal { children, .. } = self.node(root) {
+            if children.len() == 1 {
+                self.root = children[0];
+                self.remove_node(root);
+            }
+        }
+        Some(old)
+    }
+
+    fn remove_at(&mut self, id: u32, key: &K) -> Option<V> {
+        let (i, child) = match self.node(id) {
+            Node::Leaf { keys, .. } => {
+                let i = keys.binary_search(key).ok()?;
+                return match self.node_mut(id) {
+                    Node::Leaf { keys, values } => {
+                        keys.remove(i);
+                        Some(values.remove(i))
+                    }
+                    Node::Internal { .. } => unreachable!(),
+                };
+            }
+            Node::Internal { keys, children, .. } => {
+                let i = child_index(keys, key);
+                (i, children[i])
+            }
+        };
+        let old = self.remove_at(child, key)?;
+        if let Node::Internal { counts, .. } = self.node_mut(id) {
+            counts[i] -= 1;
+        }
+        if self.node(child).size() < MIN_SIZE {
+            self.rebalance(id, i);
+        }
+        Some(old)
+    }
+
+    //merge the `i`th child of `parent` with a sibling, splitting them evenly again if too large.
+    fn rebalance(&mut self, parent: u32, i: usize) {
+        let (left, right, sep) = match self.node(parent) {
+            Node::Internal { keys, children, .. } => {
+                let i = if i + 1 < children.len() { i } else { i - 1 };
+                (children[i], children[i + 1], keys[i].clone())
+            }
+            Node::Leaf { .. } => unreachable!(),
+        };
+        let right_node = self.remove_node(right);
+        let node = self.node_mut(left);
+        node.absorb(sep, right_node);
+        let split = if node.size() > NODE_SIZE {
+            let (sep, right_node) = node.split();
+            Some((sep, right_node))
+        } else {
+            None
+        };
+        let left_len = node.len();
+        let split = split.map(|(sep, right_node)| {
+            //the page is kept, not to be deleted on flush.
+            self.removed.pop();
+            let right_len = right_node.len();
+            self.cache.insert(right, Page { node: right_node, dirty: true });
+            (sep, right_len)
+        });
+        if let Node::Internal { keys, children, counts } = self.node_mut(parent) {
+            let i = children.iter().position(|&child| child == left).unwrap();
+            counts[i] = left_len;
+            match split {
+                Some((sep, right_len)) => {
+                    keys[i] = sep;
+                    counts[i + 1] = right_len;
+                }
+                None => {
+                    keys.remove(i);
+                    children.remove(i + 1);
+                    counts.remove(i + 1);
+                }
+            }
+        }
+    }
+
+    ///Number of the keys less than `key`.
+    pub fn rank(&mut self, key: &K) -> u32 {
+        let mut id = self.root;
+        let mut rank = 0;
+        loop {
+            match self.node(id) {
+                Node::Internal { keys, children, counts } => {
+                    let i = child_index(keys, key);
+                    rank += counts[..i].iter().sum::<u32>();
+                    id = children[i];
+                }
+                Node::Leaf { keys, .. } => return rank + keys.partition_point(|k| k < key) as u32,
+            }
+        }
+    }
+
+    ///The entry at `index` in key order.
+    pub fn nth(&mut self, index: u32) -> Option<(&K, &V)> {
+        if index >= self.len {
+            return None;
+        }
+        let mut id = self.root;
+        let mut index = index;
+        while let Node::Internal { children, counts, .. } = self.node(id) {
+            let mut i = 0;
+            while index >= counts[i] {
+                index -= counts[i];
+                i += 1;
+            }
+            id = children[i];
+        }
+        match self.node(id) {
+            Node::Leaf { keys, values } => Some((&keys[index as usize], &values[index as usize])),
+            Node::Internal { .. } => unreachable!(),
+        }
+    }
+
+    pub fn first(&mut self) -> Option<(&K, &V)> {
+        self.nth(0)
+    }
+
+    pub fn last(&mut self) -> Option<(&K, &V)> {
+        self.nth(self.len.checked_sub(1)?)
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let key = self.first()?.0.clone();
+        let value = self.remove(&key)?;
+        Some((key, value))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let key = self.last()?.0.clone();
+        let value = self.remove(&key)?;
+        Some((key, value))
+    }
+}
+
+impl<K, V> SortedMapStore<K, V>
+where
+    for<'a> K: Decoder<'a> + Encoder + Ord + Clone + 'static,
+    for<'a> V: Decoder<'a> + Encoder + Clone + 'static,
+{
+    //the node at `id`, read from storage when not cached.
+    fn load(&self, id: u32) -> Node<K, V> {
+        match self.cache.get(&id) {
+            Some(page) => page.node.clone(),
+            None => database::get(gen_key(&self.prefix, id)).unwrap(),
+        }
+    }
+
+    pub fn iter(&self) -> Range<'_, K, V> {
+        self.range(..)
+    }
+
+    ///Iterate over the entries with key within `range` in key order.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V> {
+        let mut stack = Vec::new();
+        let mut id = self.root;
+        loop {
+            let node = self.load(id);
+            let (i, child) = match &node {
+                Node::Internal { keys, children, .. } => {
+                    let i = match range.start_bound() {
+                        Bound::Included(start) | Bound::Excluded(start) => child_index(keys, start),
+                        Bound::Unbounded => 0,
+                    };
+                    (i + 1, Some(children[i]))
+                }
+                Node::Leaf { keys, .. } => match range.start_bound() {
+                    Bound::Included(start) => (keys.partition_point(|k| k < start), None),
+                    Bound::Excluded(start) => (keys.partition_point(|k| k <= start), None),
+                    Bound::Unbounded => (0, None),
+                },
+            };
+            stack.push((node, i));
+            match child {
+                Some(child) => id = child,
+                None => break,
+            }
+        }
+        Range { map: self, stack, end: range.end_bound().cloned() }
+    }
+}
+
+///Iterator over the entries of a `SortedMapStore`, created by `SortedMapStore::iter` and
+///`SortedMapStore::range`.
+///
+///Pages not in cache are read from storage without being cached.
+pub struct Range<'a, K: Encoder, V: Encoder> {
+    map: &'a SortedMapStore<K, V>,
+    stack: Vec<(Node<K, V>, usize)>, //node, next entry or child
+    end: Bound<K>,
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V>
+where
+    for<'b> K: Decoder<'b> + Encoder + Ord + Clone + 'static,
+    for<'b> V: Decoder<'b> + Encoder + Clone + 'static,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            let (node, i) = self.stack.last_mut()?;
+            match node {
+                Node::Leaf { keys, values } if *i < keys.len() => {
+                    let key = &keys[*i];
+                    let in_range = match &self.end {
+                        Bound::Included(end) => key <= end,
+                        Bound::Excluded(end) => key < end,
+                        Bound::Unbounded => true,
+                    };
+                    if !in_range {
+                        self.stack.clear();
+                        return None;
+                    }
+                    *i += 1;
+                    return Some((key.clone(), values[*i - 1].clone()));
+                }
+                Node::Internal { children, .. } if *i < children.len() => {
+                    let child = children[*i];
+                    *i += 1;
+                    let node = self.map.load(child);
+                    self.stack.push((node, 0));
+                }
+                _ => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SortedMapStore<K, V>
+where
+    for<'b> K: Decoder<'b> + Encoder + Ord + Clone + 'static,
+    for<'b> V: Decoder<'b> + Encoder + Clone + 'static,
+{
+    type Item = (K, V);
+    type IntoIter = Range<'a, K, V>;
+
+    fn into_iter(self) -> Range<'a, K, V> {
+        self.iter()
+    }
+}
+
+///Persistent ordered set, a `SortedMapStore` without values.
+/// # Example
+/// ```no_run
+/// # use ontio_std::database::SortedSetStore;
+/// let mut expiry: SortedSetStore<(u64, u32)> = SortedSetStore::open(b"expiry");
+/// expiry.insert((1000, 1));
+/// expiry.insert((900, 2));
+/// assert_eq!(expiry.pop_first(), Some((900, 2)));
+/// ```
+pub struct SortedSetStore<K: Encoder> {
+    map: SortedMapStore<K, ()>,
+}
+
+impl<K> SortedSetStore<K>
+where
+    for<'a> K: Decoder<'a> + Encoder + Ord + Clone + 'static,
+{
+    pub fn open(prefix: &[u8]) -> SortedSetStore<K> {
+        SortedSetStore { map: SortedMapStore::open(prefix) }
+    }
+
+    pub fn len(&self) -> u32 {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    ///Insert `key`, returning false if already present.
+    pub fn insert(&mut self, key: K) -> bool {
+        self.map.insert(key, ()).is_none()
+    }
+
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.map.remove(key).is_some()
+    }
+
+    pub fn contains(&mut self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    ///Number of the keys less than `key`.
+    pub fn rank(&mut self, key: &K) -> u32 {
+        self.map.rank(key)
+    }
+
+    pub fn nth(&mut self, index: u32) -> Option<&K> {
+        self.map.nth(index).map(|(key, _)| key)
+    }
+
+    pub fn first(&mut self) -> Option<&K> {
+        self.map.first().map(|(key, _)| key)
+    }
+
+    pub fn last(&mut self) -> Option<&K> {
+        self.map.last().map(|(key, _)| key)
+    }
+
+    pub fn pop_first(&mut self) -> Option<K> {
+        self.map.pop_first().map(|(key, _)| key)
+    }
+
+    pub fn pop_last(&mut self) -> Option<K> {
+        self.map.pop_last().map(|(key, _)| key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = K> + '_ {
+        self.map.iter().map(|(key, _)| key)
+    }
+
+    ///Iterate over the keys within `range` in order.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> impl Iterator<Item = K> + '_ {
+        self.map.range(range).map(|(key, _)| key)
+    }
+
+    ///Write the modified pages to storage.
+    pub fn flush(&mut self) {
+        self.map.flush();
+    }
+}
+
+#[test]
+fn test_sorted_map() {
+    crate::mock::build_runtime();
+    let mut expect = BTreeMap::new();
+    let mut map: SortedMapStore<u32, u64> = SortedMapStore::open(b"sorted");
+    assert_eq!(map.first(), None);
+    assert_eq!(map.iter().next(), None);
+    for round in 0..4 {
+        for _ in 0..1500 {
+            let key = rand::random::<u32>() % 2000;
+            match rand::random::<u8>() % 3 {
+                0 | 1 => assert_eq!(map.insert(key, round), expect.insert(key, round)),
+                _ => assert_eq!(map.remove(&key), expect.remove(&key)),
+            }
+        }
+        drop(map);
+        map = SortedMapStore::open(b"sorted");
+        assert_eq!(map.len() as usize, expect.len());
+        assert_eq!(map.iter().collect::<Vec<_>>(), expect.clone().into_iter().collect::<Vec<_>>());
+        let range = map.range(500..=1000).collect::<Vec<_>>();
+        assert_eq!(range, expect.range(500..=1000).map(|(k, v)| (*k, *v)).collect::<Vec<_>>());
+        let range = (Bound::Excluded(700), Bound::Unbounded);
+        let range = map.range(range).take(10).collect::<Vec<_>>();
+        let expect_range = expect.range((Bound::Excluded(700), Bound::Unbounded));
+        assert_eq!(range, expect_range.take(10).map(|(k, v)| (*k, *v)).collect::<Vec<_>>());
+        for key in [0, 333, 1000, 1999, 2500] {
+            assert_eq!(map.get(&key), expect.get(&key));
+            assert_eq!(map.rank(&key) as usize, expect.range(..key).count());
+        }
+        let (key, val) = expect.iter().nth(expect.len() / 3).unwrap();
+        assert_eq!(map.nth(expect.len() as u32 / 3), Some((key, val)));
+        assert_eq!(map.last(), expect.iter().next_back());
+    }
+    let first = *map.first().unwrap().0;
+    *map.get_mut(&first).unwrap() = 100;
+    assert_eq!(map.pop_first().map(|(_, val)| val), Some(100));
+    expect.pop_first();
+    assert_eq!(map.pop_last(), expect.pop_last());
+    while map.pop_first().is_some() {}
+    assert!(map.is_empty());
+    drop(map);
+
+    // only the header and the empty root are left.
+    assert_eq!(crate::runtime::storage_scan(b"sorted", b"", u32::MAX).len(), 2);
+}
+
+#[test]
+fn test_sorted_set() {
+    crate::mock::build_runtime();
+    let mut set: SortedSetStore<(u64, u32)> = SortedSetStore::open(b"expiry");
+    for id in 0..100 {
+        assert!(set.insert((1000 - id as u64 * 10, id)));
+    }
+    assert!(!set.insert((1000, 0)));
+    set.flush();
+
+    let mut set: SortedSetStore<(u64, u32)> = SortedSetStore::open(b"expiry");
+    assert_eq!(set.len(), 100);
+    assert!(set.contains(&(500, 50)));
+    assert_eq!(set.rank(&(500, 50)), 49);
+    assert_eq!(set.nth(0), Some(&(10, 99)));
+    assert_eq!(set.range(..(40, 0)).collect::<Vec<_>>(), vec![(10, 99), (20, 98), (30, 97)]);
+    assert_eq!(set.pop_first(), Some((10, 99)));
+    assert!(set.remove(&(20, 98)));
+    assert_eq!(set.first(), Some(&(30, 97)));
+    assert_eq!(set.pop_last(), Some((1000, 0)));
+    assert_eq!(set.iter().count(), 97);
+}
@@ -0,0 +1,129 @@
+use crate::abi::{Decoder, Encoder};
+use crate::database;
+use crate::prelude::*;
+
+///Single typed storage slot, loaded on first access and written back on drop if modified.
+/// # Example
+/// ```no_run
+/// # use ontio_std::database::StorageValue;
+/// # use ontio_std::types::U128;
+/// let mut total: StorageValue<U128> = StorageValue::new("total_supply");
+/// *total.get_mut_or_default() += 100;
+/// ```
+pub struct StorageValue<T: Encoder> {
+    key: Vec<u8>,
+    loaded: bool,
+    dirty: bool,
+    value: Option<T>,
+}
+
+impl<T: Encoder> Drop for StorageValue<T> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+impl<T: Encoder> StorageValue<T> {
+    pub fn new<K: AsRef<[u8]>>(key: K) -> StorageValue<T> {
+        StorageValue { key: key.as_ref().to_vec(), loaded: false, dirty: false, value: None }
+    }
+
+    pub fn set(&mut self, value: T) {
+        self.value = Some(value);
+        self.loaded = true;
+        self.dirty = true;
+    }
+
+    pub fn remove(&mut self) {
+        self.value = None;
+        self.loaded = true;
+        self.dirty = true;
+    }
+
+    ///Write the value to storage if modified.
+    pub fn flush(&mut self) {
+        if self.dirty {
+            match self.value {
+                Some(ref value) => database::put(&self.key, value),
+                None => database::delete(&self.key),
+            }
+            self.dirty = false;
+        }
+    }
+}
+
+impl<T> StorageValue<T>
+where
+    for<'a> T: Decoder<'a> + Encoder + 'static,
+{
+    fn load(&mut self) {
+        if !self.loaded {
+            self.value = database::get(&self.key);
+            self.loaded = true;
+        }
+    }
+
+    pub fn get(&mut self) -> Option<&T> {
+        self.load();
+        self.value.as_ref()
+    }
+
+    ///Mutable access to the value, which is written back on flush.
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.load();
+        if self.value.is_some() {
+            self.dirty = true;
+        }
+        self.value.as_mut()
+    }
+
+    pub fn get_mut_or_default(&mut self) -> &mut T
+    where
+        T: Default,
+    {
+        self.load();
+        self.dirty = true;
+        self.value.get_or_insert_with(T::default)
+    }
+
+    pub fn is_some(&mut self) -> bool {
+        self.get().is_some()
+    }
+}
+
+impl<T> StorageValue<T>
+where
+    for<'a> T: Decoder<'a> + Encoder + Clone + Default + 'static,
+{
+    pub fn get_or_default(&mut self) -> T {
+        self.get().cloned().unwrap_or_default()
+    }
+}
+
+#[test]
+fn test_storage_value() {
+    use crate::types::U128;
+    let handle = crate::mock::build_runtime();
+    {
+        let mut total: StorageValue<U128> = StorageValue::new("total");
+        assert_eq!(total.get(), None);
+        *total.get_mut_or_default() += 5;
+        *total.get_mut().unwrap() += 5;
+        assert!(handle.storage_read(b"total").is_none());
+    }
+    let mut total: StorageValue<U128> = StorageValue::new("total");
+    assert_eq!(total.get_or_default(), U128::new(10));
+    total.set(U128::new(1));
+    total.flush();
+    assert_eq!(handle.storage_read(b"total"), Some(1u128.to_le_bytes().to_vec()));
+    // reading only does not write back.
+    handle.storage_put("total", U128::new(2));
+    assert_eq!(total.get(), Some(&U128::new(1)));
+    drop(total);
+    assert_eq!(handle.storage_read(b"total"), Some(2u128.to_le_bytes().to_vec()));
+
+    let mut total: StorageValue<U128> = StorageValue::new("total");
+    total.remove();
+    drop(total);
+    assert!(handle.storage_read(b"total").is_none());
+}
@@ -76,6 +76,8 @@ pub mod abi;
 pub mod console;
 ///The contract module provides the method to call the asset ont, ong on the ontology chain.
 pub mod contract;
+///The crypto module provides the hash functions and the address derivations used for the interoperation with other chains.
+pub mod crypto;
 ///The database module provides the interface to save the data in the contract to the chain and query the data from the chain.
 pub mod database;
 ///The runtime module provides an interface to interact with the chain in the contract
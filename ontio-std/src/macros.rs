@@ -6,7 +6,7 @@ pub use ontio_codegen::keccak256;
 #[cfg(test)]
 mod tests {
     use crate as ontio_std;
-    use ontio_std::abi::{Decoder, Encoder};
+    use ontio_std::abi::{Decoder, Encoder, VmValueDecoder, VmValueEncoder};
     use ontio_std::types::to_hex_string;
 
     #[derive(Encoder, Decoder)]
@@ -21,6 +21,56 @@ mod tests {
         Oep4(Oep4),
     }
 
+    #[derive(Encoder, Decoder, Debug, PartialEq)]
+    struct Pair(u32, bool);
+
+    #[derive(Encoder, Decoder, Debug, PartialEq)]
+    struct Nothing;
+
+    #[derive(Encoder, Decoder, Debug, PartialEq)]
+    struct Named<'a, T> {
+        name: &'a str,
+        values: Vec<T>,
+        #[codec(skip)]
+        cache: Option<T>,
+        #[codec(default)]
+        version: u32,
+    }
+
+    #[derive(Encoder, Decoder, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Point(u32, u32),
+        Rect { width: u32, height: u32 },
+    }
+
+    #[derive(Encoder, Decoder, Debug, PartialEq)]
+    #[codec(tag = "index")]
+    enum Op {
+        Stop,
+        Push(u64),
+        #[codec(index = 300)]
+        Jump {
+            offset: u32,
+        },
+        Call(String),
+    }
+
+    #[derive(VmValueEncoder, VmValueDecoder, Debug, PartialEq)]
+    struct Transfer<'a> {
+        from: Address,
+        to: &'a Address,
+        amount: U128,
+        memo: (&'a str, Vec<u8>),
+        #[codec(skip)]
+        fee: u64,
+        #[codec(default)]
+        tx: H256,
+    }
+
+    #[derive(VmValueEncoder, VmValueDecoder, Debug, PartialEq)]
+    struct Batch(bool, H256);
+
     use ontio_std::prelude::*;
 
     #[ontio_std::macros::contract]
@@ -42,6 +92,225 @@ mod tests {
         fn SliceParam(&self, from: &[Address]) {}
     }
 
+    mod result_contract {
+        use crate as ontio_std;
+        use ontio_std::prelude::*;
+
+        pub struct NotOwner;
+
+        impl ontio_std::abi::ContractError for NotOwner {
+            fn code(&self) -> u32 {
+                ontio_std::abi::USER_ERROR_CODE_START
+            }
+
+            fn message(&self) -> String {
+                "not owner".to_string()
+            }
+        }
+
+        #[ontio_std::macros::contract]
+        trait ResultContract {
+            fn set_owner(&mut self, owner: Address) -> Result<bool, NotOwner>;
+            fn clear(&mut self);
+        }
+
+        struct ResultContractInstance;
+
+        impl ResultContract for ResultContractInstance {
+            fn set_owner(&mut self, owner: Address) -> Result<bool, NotOwner> {
+                if owner.is_zero() {
+                    return Err(NotOwner);
+                }
+                Ok(true)
+            }
+
+            fn clear(&mut self) {}
+        }
+
+        fn dispatch_err(payload: &[u8]) -> String {
+            use ontio_std::abi::Dispatcher;
+            let payload = payload.to_vec();
+            let err = std::panic::catch_unwind(move || {
+                ResultContractDispatcher::new(ResultContractInstance).dispatch(&payload)
+            })
+            .unwrap_err();
+            err.downcast_ref::<String>().unwrap().clone()
+        }
+
+        #[test]
+        fn dispatch_result() {
+            use ontio_std::abi::{Dispatcher, Sink};
+            let mut dispatcher = ResultContractDispatcher::new(ResultContractInstance);
+            let mut sink = Sink::new(16);
+            sink.write(("set_owner", Address::repeat_byte(1)));
+            assert_eq!(dispatcher.dispatch(sink.bytes()), vec![1]);
+
+            let mut sink = Sink::new(16);
+            sink.write("clear");
+            assert_eq!(dispatcher.dispatch(sink.bytes()), Vec::<u8>::new());
+
+            let mut sink = Sink::new(16);
+            sink.write(("set_owner", Address::zero()));
+            assert_eq!(dispatch_err(sink.bytes()), r#"{"code":1000,"message":"not owner"}"#);
+
+            let mut sink = Sink::new(16);
+            sink.write("set_owner");
+            assert_eq!(
+                dispatch_err(sink.bytes()),
+                r#"{"code":1,"message":"failed to decode argument"}"#
+            );
+
+            let mut sink = Sink::new(16);
+            sink.write("unknown");
+            assert_eq!(
+                dispatch_err(sink.bytes()),
+                r#"{"code":2,"message":"invoke unsupported method"}"#
+            );
+        }
+
+        #[test]
+        fn client() {
+            use ontio_std::abi::Dispatcher;
+            use ontio_std::mock::build_runtime;
+            let contract = Address::repeat_byte(9);
+            build_runtime().on_contract_call_result(move |addr, data| {
+                assert_eq!(addr, &contract);
+                let data = data.to_vec();
+                std::panic::catch_unwind(move || {
+                    ResultContractDispatcher::new(ResultContractInstance).dispatch(&data)
+                })
+                .map_err(|err| err.downcast_ref::<String>().unwrap().as_bytes().to_vec())
+            });
+            let client = ResultContractClient::new(contract);
+            assert!(client.set_owner(Address::repeat_byte(1)));
+            client.clear();
+            let err = std::panic::catch_unwind(|| client.set_owner(Address::zero())).unwrap_err();
+            assert_eq!(err.downcast_ref::<String>().unwrap(), "call contract failed");
+
+            build_runtime().on_contract_call(|_, _| Vec::new());
+            let err = std::panic::catch_unwind(|| client.set_owner(Address::repeat_byte(1)));
+            assert_eq!(
+                err.unwrap_err().downcast_ref::<String>().unwrap(),
+                r#"{"code":4,"message":"failed to decode return value"}"#
+            );
+        }
+    }
+
+    #[test]
+    fn abi() {
+        assert_eq!(
+            TEST_CONTRACT_ABI,
+            concat!(
+                r#"{"contract":"TestContract","actions":["#,
+                r#"{"name":"mut_self","parameters":[{"name":"owner","type":"Address"}],"return":"bool"},"#,
+                r#"{"name":"ref_self","parameters":[],"return":"String"},"#,
+                r#"{"name":"multi_param","parameters":[{"name":"from","type":"Address"},{"name":"to","type":"Address"},{"name":"amount","type":"U128"}],"return":"bool"},"#,
+                r#"{"name":"ref_param","parameters":[{"name":"owner","type":"Address"}],"return":"bool"},"#,
+                r#"{"name":"slice_param","parameters":[{"name":"addrs","type":"[Address]"}],"return":"bool"},"#,
+                r#"{"name":"mut_param","parameters":[{"name":"owner","type":"Address"}],"return":"bool"},"#,
+                r#"{"name":"mut_slice_param","parameters":[{"name":"owner","type":"[Address]"}],"return":"bool"},"#,
+                r#"{"name":"str_param","parameters":[{"name":"owner","type":"str"}],"return":"bool"}],"#,
+                r#""events":["#,
+                r#"{"name":"Event","parameters":[{"name":"from","type":"Address"},{"name":"to","type":"Address"},{"name":"amount","type":"U128"}]},"#,
+                r#"{"name":"RefParam","parameters":[{"name":"from","type":"Address"},{"name":"to","type":"Address"},{"name":"amount","type":"U128"}]},"#,
+                r#"{"name":"SliceParam","parameters":[{"name":"from","type":"[Address]"}]}]}"#
+            )
+        );
+        assert_eq!(
+            result_contract::RESULT_CONTRACT_ABI,
+            concat!(
+                r#"{"contract":"ResultContract","actions":["#,
+                r#"{"name":"set_owner","parameters":[{"name":"owner","type":"Address"}],"return":"bool"},"#,
+                r#"{"name":"clear","parameters":[],"return":"()"}],"events":[]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn codec() {
+        use ontio_std::abi::{Sink, Source};
+        fn roundtrip<T: Encoder + for<'a> Decoder<'a>>(val: T) -> T {
+            let mut sink = Sink::new(16);
+            sink.write(&val);
+            Source::new(sink.bytes()).read().unwrap()
+        }
+        assert_eq!(roundtrip(Pair(7, true)), Pair(7, true));
+        assert_eq!(roundtrip(Nothing), Nothing);
+        for shape in [Shape::Empty, Shape::Point(1, 2), Shape::Rect { width: 3, height: 4 }] {
+            let mut sink = Sink::new(16);
+            sink.write(&shape);
+            assert_eq!(Source::new(sink.bytes()).read::<Shape>().unwrap(), shape);
+        }
+        let mut sink = Sink::new(16);
+        sink.write(Token::Oep4(Oep4 { from: 1, to: 2, amt: 3 }));
+        let Token::Oep4(token) = Source::new(sink.bytes()).read().unwrap();
+        assert_eq!((token.from, token.to, token.amt), (1, 2, 3));
+
+        let named = Named { name: "ont", values: vec![1u8, 2], cache: Some(3u8), version: 2 };
+        let mut sink = Sink::new(16);
+        sink.write(&named);
+        assert_eq!(sink.bytes(), &[3, b'o', b'n', b't', 2, 1, 2, 2, 0, 0, 0]);
+        let decoded: Named<u8> = Source::new(sink.bytes()).read().unwrap();
+        assert_eq!(decoded, Named { cache: None, ..named });
+        // the data of the version without the appended field.
+        let old = &sink.bytes()[..7];
+        let decoded: Named<u8> = Source::new(old).read().unwrap();
+        assert_eq!(decoded, Named { name: "ont", values: vec![1, 2], cache: None, version: 0 });
+
+        let mut sink = Sink::new(16);
+        sink.write("Circle");
+        assert!(Source::new(sink.bytes()).read::<Shape>().is_err());
+
+        let mut sink = Sink::new(16);
+        sink.write([Op::Stop, Op::Push(1), Op::Jump { offset: 2 }, Op::Call("f".to_string())]);
+        assert_eq!(
+            sink.bytes(),
+            &[0, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0xfd, 0x2c, 1, 2, 0, 0, 0, 0xfd, 0x2d, 1, 1, b'f']
+        );
+        let ops: (Op, Op, Op, Op) = Source::new(sink.bytes()).read().unwrap();
+        assert_eq!((ops.0, ops.1), (Op::Stop, Op::Push(1)));
+        assert_eq!((ops.2, ops.3), (Op::Jump { offset: 2 }, Op::Call("f".to_string())));
+        assert!(Source::new(&[2]).read::<Op>().is_err());
+    }
+
+    #[test]
+    fn vm_value() {
+        use ontio_std::abi::{VmValueBuilder, VmValueParser};
+        let mut builder = VmValueBuilder::new();
+        builder.write(Batch(true, H256::repeat_byte(3)));
+        let mut expected = VmValueBuilder::new();
+        let mut nested = expected.list();
+        nested.bool(true);
+        nested.h256(&H256::repeat_byte(3));
+        nested.finish();
+        assert_eq!(builder.bytes(), expected.bytes());
+
+        let to = Address::repeat_byte(2);
+        let transfer = Transfer {
+            from: Address::repeat_byte(1),
+            to: &to,
+            amount: U128::new(100),
+            memo: ("rent", vec![1, 2]),
+            fee: 7,
+            tx: H256::repeat_byte(4),
+        };
+        let mut builder = VmValueBuilder::new();
+        builder.write(&transfer);
+        builder.write(Batch(false, H256::zero()));
+        // a transfer encoded before `tx` was added.
+        builder.write((transfer.from, &to, U128::new(100), ("rent", vec![1u8, 2])));
+        let bytes = builder.bytes();
+        let mut parser = VmValueParser::new(&bytes);
+        let (decoded, batch, old): (Transfer, Batch, Transfer) = parser.read().unwrap();
+        assert_eq!(
+            old,
+            Transfer { fee: 0, tx: H256::zero(), memo: ("rent", vec![1, 2]), ..transfer }
+        );
+        assert_eq!(decoded, Transfer { fee: 0, ..transfer });
+        assert_eq!(batch, Batch(false, H256::zero()));
+        assert!(VmValueParser::new(&bytes).read::<(Batch, Batch, Batch)>().is_err());
+    }
+
     #[test]
     fn base58() {
         const _ADDR: Address = ontio_std::macros::base58!("AFmseVrdL9f9oyCzZefL9tG6UbvhPbdYzM");
@@ -0,0 +1,342 @@
+use super::contract_mock::{
+    authorize_key, native_asset_invoke, native_governance_invoke, unbound_ong_key,
+};
+use super::runtime::execute;
+use super::{build_runtime, RuntimeHandle};
+use crate::abi::{Dispatcher, Encoder, Sink, Source};
+use crate::contract::{governance, ong, ont};
+use crate::runtime;
+use crate::types::{Address, U128};
+use std::rc::Rc;
+
+/// In-process chain hosting several contracts on top of the mock runtime.
+///
+/// Every contract is deployed at its own address with isolated storage, `runtime::call_contract`
+/// to a deployed address executes the target contract, with `runtime::address`, `runtime::caller`
+/// and `runtime::entry_address` switched for the duration of the nested call. Calls to the
+/// addresses not deployed are still served by `RuntimeHandle::on_contract_call`.
+///
+/// # Example
+/// ```no_run
+/// # use ontio_std::mock::MockChain;
+/// # use ontio_std::abi::Sink;
+/// # use ontio_std::types::Address;
+/// # fn token_invoke() {}
+/// # fn bridge_invoke() {}
+/// let chain = MockChain::new();
+/// let token = Address::repeat_byte(1);
+/// let bridge = Address::repeat_byte(2);
+/// chain.deploy(&token, token_invoke).deploy(&bridge, bridge_invoke);
+/// let mut sink = Sink::new(16);
+/// sink.write("lock");
+/// let output = chain.invoke(&bridge, sink.bytes()).unwrap();
+/// ```
+pub struct MockChain {
+    handle: RuntimeHandle,
+}
+
+impl MockChain {
+    pub fn new() -> Self {
+        MockChain { handle: build_runtime() }
+    }
+
+    /// The handle of the underlying runtime, for the environment shared by all the contracts
+    /// such as block height, timestamp and witnesses.
+    pub fn runtime(&self) -> &RuntimeHandle {
+        &self.handle
+    }
+
+    /// Deploy the contract entry `invoke` at `addr`, replacing the contract deployed before.
+    pub fn deploy(&self, addr: &Address, invoke: impl Fn() + 'static) -> &Self {
+        self.handle.inner.borrow_mut().contracts.insert(*addr, Rc::new(invoke));
+        self
+    }
+
+    /// Deploy a contract served by a dispatcher, `new` creates the dispatcher for each execution.
+    pub fn deploy_dispatcher<D: Dispatcher>(
+        &self, addr: &Address, new: impl Fn() -> D + 'static,
+    ) -> &Self {
+        self.deploy(addr, move || {
+            let mut dispatcher = new();
+            runtime::ret(&dispatcher.dispatch(&runtime::input()))
+        })
+    }
+
+    /// Execute the contract at `addr` as the entry of a transaction with `input` as payload.
+    ///
+    /// Returns the bytes passed to `runtime::ret`, or the panic message if the contract aborts.
+    /// The gas used is reported by `RuntimeHandle::gas_used`.
+    pub fn invoke(&self, addr: &Address, input: &[u8]) -> Result<Vec<u8>, String> {
+        let entry = self.handle.inner.borrow().contracts.get(addr).cloned();
+        let entry = entry.unwrap_or_else(|| panic!("no contract deployed at {:?}", addr));
+        let frame = {
+            let mut inner = self.handle.inner.borrow_mut();
+            inner.entry_address = *addr;
+            inner.gas.used = 0;
+            let caller = inner.caller;
+            inner.enter(addr, &caller, input)
+        };
+        let res = execute(&self.handle.inner, || entry());
+        self.handle.inner.borrow_mut().leave(frame);
+        res
+    }
+
+    /// Deploy the simulated ONT, ONG and governance native contracts at their addresses.
+    pub fn deploy_native(&self) -> &Self {
+        self.deploy(&ont::ONT_CONTRACT_ADDRESS, native_asset_invoke)
+            .deploy(&ong::ONG_CONTRACT_ADDRESS, native_asset_invoke)
+            .deploy(&governance::GOV_CONTRACT_ADDRESS, native_governance_invoke)
+    }
+
+    /// Set the balance of `addr` in the native asset at `asset`, the amount is in the `V2` precision.
+    pub fn set_native_balance(&self, asset: &Address, addr: &Address, amount: U128) -> &Self {
+        self.storage_put(asset, addr, amount)
+    }
+
+    /// Balance of `addr` in the native asset at `asset`, in the `V2` precision.
+    pub fn native_balance(&self, asset: &Address, addr: &Address) -> U128 {
+        let balance = self.storage_read(asset, addr.as_bytes());
+        balance.map(|val| Source::new(&val).read().unwrap()).unwrap_or_default()
+    }
+
+    /// Set the unbound ONG of `addr` paid by the governance `withdrawOng`, in the `V2` precision.
+    pub fn set_unbound_ong(&self, addr: &Address, amount: U128) -> &Self {
+        let gov = &governance::GOV_CONTRACT_ADDRESS;
+        let balance = self.native_balance(&ong::ONG_CONTRACT_ADDRESS, gov);
+        self.set_native_balance(&ong::ONG_CONTRACT_ADDRESS, gov, balance + amount);
+        self.storage_put(gov, unbound_ong_key(addr), amount)
+    }
+
+    /// ONT of `addr` authorized to `peer` in the governance contract, and the ONT unauthorized
+    /// and ready to withdraw.
+    pub fn authorization(&self, peer: &str, addr: &Address) -> (U128, U128) {
+        let info = self.storage_read(&governance::GOV_CONTRACT_ADDRESS, &authorize_key(peer, addr));
+        info.map(|val| Source::new(&val).read().unwrap()).unwrap_or_default()
+    }
+
+    pub fn storage_read(&self, addr: &Address, key: &[u8]) -> Option<Vec<u8>> {
+        self.handle.inner.borrow_mut().storage_of(addr).get(key).cloned()
+    }
+
+    pub fn storage_put<K: AsRef<[u8]>, T: Encoder>(&self, addr: &Address, key: K, val: T) -> &Self {
+        let mut sink = Sink::new(12);
+        sink.write(val);
+        self.handle.inner.borrow_mut().storage_of(addr).insert(key.as_ref().to_vec(), sink.into());
+        self
+    }
+}
+
+impl Default for MockChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+fn context_contract() {
+    use crate::database;
+    let input = runtime::input();
+    let mut source = Source::new(&input);
+    match source.read().unwrap() {
+        "context" => {
+            let mut sink = Sink::new(64);
+            sink.write((runtime::address(), runtime::caller(), runtime::entry_address()));
+            runtime::ret(sink.bytes())
+        }
+        "put" => database::put("key", source.read::<u64>().unwrap()),
+        "get" => {
+            let mut sink = Sink::new(8);
+            sink.write(database::get::<_, u64>("key").unwrap_or(0));
+            runtime::ret(sink.bytes())
+        }
+        "abort" => runtime::panic("aborted"),
+        "put_abort" => {
+            database::put("key", source.read::<u64>().unwrap());
+            runtime::notify(b"put");
+            runtime::panic("aborted")
+        }
+        "burn" => loop {
+            runtime::sha256(b"gas");
+        },
+        "put_call" => {
+            database::put("key", source.read::<u64>().unwrap());
+            let target: Address = source.read().unwrap();
+            let payload: &[u8] = source.read().unwrap();
+            let _ = runtime::try_call_contract(&target, payload);
+        }
+        "call_abort" => {
+            let target: Address = source.read().unwrap();
+            let payload: &[u8] = source.read().unwrap();
+            runtime::call_contract(&target, payload);
+            runtime::panic("aborted")
+        }
+        "call" => {
+            let target: Address = source.read().unwrap();
+            let payload: &[u8] = source.read().unwrap();
+            let res = runtime::try_call_contract(&target, payload);
+            // the context of the caller is restored after the nested call.
+            assert_eq!(runtime::input(), input);
+            let mut sink = Sink::new(64);
+            match res {
+                Ok(output) => sink.write((true, output.as_slice())),
+                Err(err) => sink.write((false, err.output.as_slice())),
+            }
+            sink.write(runtime::address());
+            runtime::ret(sink.bytes())
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn test_storage_isolation() {
+    let chain = MockChain::new();
+    let (a, b) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    chain.deploy(&a, context_contract).deploy(&b, context_contract);
+
+    let mut sink = Sink::new(16);
+    sink.write(("put", 5u64));
+    chain.invoke(&a, sink.bytes()).unwrap();
+
+    let mut sink = Sink::new(16);
+    sink.write("get");
+    assert_eq!(chain.invoke(&a, sink.bytes()), Ok(5u64.to_le_bytes().to_vec()));
+    assert_eq!(chain.invoke(&b, sink.bytes()), Ok(0u64.to_le_bytes().to_vec()));
+
+    chain.storage_put(&b, "key", 7u64);
+    assert_eq!(chain.invoke(&b, sink.bytes()), Ok(7u64.to_le_bytes().to_vec()));
+    assert_eq!(chain.storage_read(&a, b"key"), Some(5u64.to_le_bytes().to_vec()));
+    assert_eq!(chain.runtime().storage_read(b"key"), None);
+}
+
+#[test]
+fn test_nested_call() {
+    let chain = MockChain::new();
+    let (a, b) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    chain.deploy(&a, context_contract).deploy(&b, context_contract);
+    chain.runtime().caller(&Address::repeat_byte(9));
+
+    let mut inner = Sink::new(8);
+    inner.write("context");
+    let mut sink = Sink::new(64);
+    sink.write(("call", &b, inner.bytes()));
+    let output = chain.invoke(&a, sink.bytes()).unwrap();
+    let mut source = Source::new(&output);
+    let (ok, nested): (bool, &[u8]) = source.read().unwrap();
+    assert!(ok);
+    assert_eq!(source.read::<Address>().unwrap(), a);
+    let context: (Address, Address, Address) = Source::new(nested).read().unwrap();
+    assert_eq!(context, (b, a, a));
+
+    // a contract calling itself sees its own storage.
+    chain.storage_put(&a, "key", 3u64);
+    let mut inner = Sink::new(8);
+    inner.write("get");
+    let mut sink = Sink::new(64);
+    sink.write(("call", &a, inner.bytes()));
+    let output = chain.invoke(&a, sink.bytes()).unwrap();
+    let (ok, nested): (bool, &[u8]) = Source::new(&output).read().unwrap();
+    assert!(ok);
+    assert_eq!(nested, 3u64.to_le_bytes());
+
+    let mut inner = Sink::new(8);
+    inner.write("context");
+    let output = chain.invoke(&b, inner.bytes()).unwrap();
+    let context: (Address, Address, Address) = Source::new(&output).read().unwrap();
+    assert_eq!(context, (b, Address::repeat_byte(9), b));
+}
+
+#[test]
+fn test_nested_call_failed() {
+    let chain = MockChain::new();
+    let (a, b) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    chain.deploy(&a, context_contract).deploy(&b, context_contract);
+
+    let mut inner = Sink::new(8);
+    inner.write("abort");
+    let mut sink = Sink::new(64);
+    sink.write(("call", &b, inner.bytes()));
+    let output = chain.invoke(&a, sink.bytes()).unwrap();
+    let (ok, nested): (bool, &[u8]) = Source::new(&output).read().unwrap();
+    assert!(!ok);
+    assert_eq!(nested, b"aborted");
+}
+
+#[test]
+fn test_deploy_dispatcher() {
+    struct Echo;
+    impl Dispatcher for Echo {
+        fn dispatch(&mut self, payload: &[u8]) -> Vec<u8> {
+            payload.to_vec()
+        }
+    }
+
+    let chain = MockChain::new();
+    let (a, b) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    chain.deploy(&a, context_contract).deploy_dispatcher(&b, || Echo);
+    assert_eq!(chain.invoke(&b, b"hello"), Ok(b"hello".to_vec()));
+
+    chain.runtime().on_contract_call(|_addr, data| data.iter().rev().cloned().collect());
+    let mut sink = Sink::new(64);
+    sink.write(("call", &Address::repeat_byte(3), b"abc".as_ref()));
+    let output = chain.invoke(&a, sink.bytes()).unwrap();
+    let (ok, nested): (bool, &[u8]) = Source::new(&output).read().unwrap();
+    assert!(ok);
+    assert_eq!(nested, b"cba");
+}
+
+#[test]
+fn test_nested_rollback() {
+    let chain = MockChain::new();
+    let (a, b) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    chain.deploy(&a, context_contract).deploy(&b, context_contract);
+    chain.storage_put(&a, "key", 1u64).storage_put(&b, "key", 1u64);
+
+    // the failed callee is rolled back while the caller goes on.
+    let mut inner = Sink::new(16);
+    inner.write(("put_abort", 2u64));
+    let mut sink = Sink::new(64);
+    sink.write(("call", &b, inner.bytes()));
+    chain.invoke(&a, sink.bytes()).unwrap();
+    assert_eq!(chain.storage_read(&b, b"key"), Some(1u64.to_le_bytes().to_vec()));
+    assert!(chain.runtime().inner.borrow().notify.is_empty());
+
+    // the succeeded callee is rolled back with the failed caller.
+    let mut inner = Sink::new(16);
+    inner.write(("put", 3u64));
+    let mut sink = Sink::new(64);
+    sink.write(("call_abort", &b, inner.bytes()));
+    assert_eq!(chain.invoke(&a, sink.bytes()), Err("aborted".to_string()));
+    assert_eq!(chain.storage_read(&b, b"key"), Some(1u64.to_le_bytes().to_vec()));
+
+    let mut sink = Sink::new(16);
+    sink.write(("put_abort", 4u64));
+    assert!(chain.invoke(&a, sink.bytes()).is_err());
+    assert_eq!(chain.storage_read(&a, b"key"), Some(1u64.to_le_bytes().to_vec()));
+}
+
+#[test]
+fn test_nested_out_of_gas() {
+    let chain = MockChain::new();
+    let (a, b) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    chain.deploy(&a, context_contract).deploy(&b, context_contract);
+    chain.storage_put(&a, "key", 1u64);
+    chain.runtime().gas_limit(10_000);
+
+    // the caller can not catch the callee running out of gas, the whole invocation is aborted.
+    let mut inner = Sink::new(8);
+    inner.write("burn");
+    let mut sink = Sink::new(64);
+    sink.write(("put_call", 2u64, &b, inner.bytes()));
+    assert_eq!(chain.invoke(&a, sink.bytes()), Err("out of gas".to_string()));
+    assert_eq!(chain.storage_read(&a, b"key"), Some(1u64.to_le_bytes().to_vec()));
+    assert_eq!(chain.runtime().gas_used(), 10_000);
+
+    // nested twice.
+    let mut middle = Sink::new(64);
+    middle.write(("put_call", 3u64, &b, inner.bytes()));
+    let mut sink = Sink::new(64);
+    sink.write(("call", &a, middle.bytes()));
+    assert_eq!(chain.invoke(&b, sink.bytes()), Err("out of gas".to_string()));
+    assert_eq!(chain.storage_read(&a, b"key"), Some(1u64.to_le_bytes().to_vec()));
+}
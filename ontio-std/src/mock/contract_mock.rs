@@ -1,38 +1,353 @@
 use super::super::prelude::*;
 use crate::abi::event_builder::TYPE_LIST;
 use crate::abi::event_builder::{TYPE_ADDRESS, TYPE_INT, TYPE_STRING};
-use crate::abi::{Decoder, Error, Source};
-use crate::types::{u128_from_neo_bytes, Address, U128};
+use crate::abi::{Decoder, Error, EventBuilder, Source};
+use crate::contract::governance::GOV_CONTRACT_ADDRESS;
+use crate::contract::{ong, ont, TransferParam};
+use crate::types::{u128_from_neo_bytes, u128_to_neo_bytes, Address, U128};
+use crate::{database, runtime};
 
 pub enum Command<'a> {
     Transfer { from: &'a Address, to: &'a Address, value: U128 },
     BalanceOf { addr: &'a Address },
+    TransferMulti { transfers: Vec<TransferParam> },
+    Approve { from: &'a Address, to: &'a Address, value: U128 },
+    Allowance { from: &'a Address, to: &'a Address },
+    TransferFrom { sender: &'a Address, from: &'a Address, to: &'a Address, value: U128 },
 }
 
-impl<'a> Decoder<'a> for Command<'a> {
+///Call to the native ONT/ONG contract, as encoded by `contract::ont` and `contract::ong`.
+pub struct NativeCall<'a> {
+    ///The call uses the `V2` methods, whose amounts are in the higher precision.
+    pub v2: bool,
+    pub command: Command<'a>,
+}
+
+fn read_amount(source: &mut Source) -> Result<U128, Error> {
+    let amt: &[u8] = source.read()?;
+    Ok(u128_from_neo_bytes(amt))
+}
+
+impl<'a> Decoder<'a> for NativeCall<'a> {
     fn decode(source: &mut Source<'a>) -> Result<Self, Error> {
         let version = source.read_byte()?; //version
-        assert_eq!(version, 0);
-        let method = source.read().unwrap();
-        match method {
+        if version != 0 {
+            return Err(Error::IrregularData);
+        }
+        let method: &str = source.read()?;
+        let (method, v2) = match method.strip_suffix("V2") {
+            Some(method) => (method, true),
+            None => (method, false),
+        };
+        let params: &[u8] = source.read()?;
+        let mut source = Source::new(params);
+        let command = match method {
             "transfer" => {
-                let param_length = source.read_varuint()?; //param length
-                assert_ne!(param_length, 0);
-                let transfer_len = source.read_native_varuint().ok().unwrap(); //transfer length
-                assert_eq!(transfer_len, 1);
-                let from = source.read_native_address().ok().unwrap();
-                let to = source.read_native_address().ok().unwrap();
-                let amt: Vec<u8> = source.read().ok().unwrap();
-                let value = u128_from_neo_bytes(amt.as_slice());
-                Ok(Command::Transfer { from, to, value })
+                let transfer_len = source.read_native_varuint()?; //transfer length
+                if transfer_len == 0 {
+                    return Err(Error::IrregularData);
+                }
+                let mut states = Vec::with_capacity(transfer_len as usize);
+                for _ in 0..transfer_len {
+                    let from = source.read_native_address()?;
+                    let to = source.read_native_address()?;
+                    let value = read_amount(&mut source)?;
+                    states.push((from, to, value));
+                }
+                match states.as_slice() {
+                    &[(from, to, value)] => Command::Transfer { from, to, value },
+                    _ => {
+                        let transfers = states
+                            .iter()
+                            .map(|&(from, to, amount)| TransferParam {
+                                from: *from,
+                                to: *to,
+                                amount,
+                            })
+                            .collect();
+                        Command::TransferMulti { transfers }
+                    }
+                }
             }
-            "balance_of" => {
-                let addr = source.read_native_address().ok().unwrap();
-                Ok(Command::BalanceOf { addr })
+            "balanceOf" | "balance_of" => {
+                let addr = source.read_native_address()?;
+                Command::BalanceOf { addr }
             }
-            _ => panic!(""),
+            "approve" => {
+                let from = source.read_native_address()?;
+                let to = source.read_native_address()?;
+                let value = read_amount(&mut source)?;
+                Command::Approve { from, to, value }
+            }
+            "allowance" => {
+                let from = source.read_native_address()?;
+                let to = source.read_native_address()?;
+                Command::Allowance { from, to }
+            }
+            "transferFrom" => {
+                let sender = source.read_native_address()?;
+                let from = source.read_native_address()?;
+                let to = source.read_native_address()?;
+                let value = read_amount(&mut source)?;
+                Command::TransferFrom { sender, from, to, value }
+            }
+            _ => return Err(Error::IrregularData),
+        };
+        Ok(NativeCall { v2, command })
+    }
+}
+
+impl<'a> Decoder<'a> for Command<'a> {
+    fn decode(source: &mut Source<'a>) -> Result<Self, Error> {
+        NativeCall::decode(source).map(|call| call.command)
+    }
+}
+
+///Ratio between the amounts of the `V2` methods and the original ones, for both ONT and ONG.
+pub const NATIVE_V2_SCALE: u128 = 1_000_000_000;
+
+fn allowance_key(from: &Address, to: &Address) -> Vec<u8> {
+    [from.as_bytes(), to.as_bytes()].concat()
+}
+
+fn native_balance(addr: &Address) -> U128 {
+    database::get(addr).unwrap_or_default()
+}
+
+//the native contract accepts both the transaction signers and the calling contract as witness,
+//the governance contract moves the deposits it has already authenticated.
+fn native_check_witness(addr: &Address) {
+    let caller = runtime::caller();
+    if !runtime::check_witness(addr) && caller != *addr && caller != GOV_CONTRACT_ADDRESS {
+        runtime::panic("authentication failed")
+    }
+}
+
+//amounts are stored in the `V2` precision.
+fn native_amount(value: U128, v2: bool) -> U128 {
+    if v2 {
+        value
+    } else {
+        value * NATIVE_V2_SCALE
+    }
+}
+
+fn native_transfer(from: &Address, to: &Address, value: U128, v2: bool) {
+    let amount = native_amount(value, v2);
+    let balance = native_balance(from);
+    if balance < amount {
+        runtime::panic("balance insufficient")
+    }
+    database::put(from, balance - amount);
+    database::put(to, native_balance(to) + amount);
+    let method = if v2 { "transferV2" } else { "transfer" };
+    EventBuilder::new().string(method).address(from).address(to).number(value).notify();
+}
+
+///Entry of the simulated ONT and ONG native contracts, to be deployed on `MockChain`.
+///
+///Balances are kept in the `V2` precision, the original methods scale the amounts by
+///`NATIVE_V2_SCALE`. Every transfer emits the event `[method, from, to, amount]`.
+pub fn native_asset_invoke() {
+    let input = runtime::input();
+    let call: NativeCall =
+        Source::new(&input).read().unwrap_or_else(|_| runtime::panic("invalid input"));
+    let v2 = call.v2;
+    let call_amount =
+        |amount: U128| if v2 { amount } else { U128::new(amount.raw() / NATIVE_V2_SCALE) };
+    let output = match call.command {
+        Command::Transfer { from, to, value } => {
+            native_check_witness(from);
+            native_transfer(from, to, value, v2);
+            vec![1]
+        }
+        Command::TransferMulti { transfers } => {
+            for state in transfers.iter() {
+                native_check_witness(&state.from);
+                native_transfer(&state.from, &state.to, state.amount, v2);
+            }
+            vec![1]
+        }
+        Command::BalanceOf { addr } => u128_to_neo_bytes(call_amount(native_balance(addr))),
+        Command::Approve { from, to, value } => {
+            native_check_witness(from);
+            database::put(allowance_key(from, to), native_amount(value, v2));
+            vec![1]
+        }
+        Command::Allowance { from, to } => {
+            let amount = database::get(allowance_key(from, to)).unwrap_or_default();
+            u128_to_neo_bytes(call_amount(amount))
+        }
+        Command::TransferFrom { sender, from, to, value } => {
+            native_check_witness(sender);
+            let key = allowance_key(from, sender);
+            let allowance: U128 = database::get(&key).unwrap_or_default();
+            let amount = native_amount(value, v2);
+            if allowance < amount {
+                runtime::panic("allowance insufficient")
+            }
+            database::put(&key, allowance - amount);
+            native_transfer(from, to, value, v2);
+            vec![1]
         }
+    };
+    runtime::ret(&output)
+}
+
+///Call to the native governance contract, as encoded by `contract::governance`.
+pub enum GovernanceCall<'a> {
+    ///Authorize `amounts` of ONT to `peers`, the deposit is taken by `transferFrom` when
+    ///`transfer_from` is set.
+    Authorize {
+        addr: &'a Address,
+        peers: Vec<&'a str>,
+        amounts: Vec<u32>,
+        transfer_from: bool,
+    },
+    UnAuthorize {
+        addr: &'a Address,
+        peers: Vec<&'a str>,
+        amounts: Vec<u32>,
+    },
+    Withdraw {
+        addr: &'a Address,
+        peers: Vec<&'a str>,
+        amounts: Vec<u32>,
+    },
+    WithdrawOng {
+        addr: &'a Address,
+    },
+}
+
+//counts and amounts are neo numbers, the native varuint of small counts included.
+fn read_count(source: &mut Source) -> Result<u32, Error> {
+    let val = read_amount(source)?.raw();
+    if val > u32::MAX as u128 {
+        return Err(Error::IrregularData);
     }
+    Ok(val as u32)
+}
+
+fn read_peers<'a>(source: &mut Source<'a>) -> Result<(&'a Address, Vec<&'a str>, Vec<u32>), Error> {
+    let addr = source.read_native_address()?;
+    let peers = (0..read_count(source)?).map(|_| source.read()).collect::<Result<Vec<_>, _>>()?;
+    let amounts =
+        (0..read_count(source)?).map(|_| read_count(source)).collect::<Result<Vec<_>, _>>()?;
+    if peers.len() != amounts.len() {
+        return Err(Error::LengthInconsistency);
+    }
+    Ok((addr, peers, amounts))
+}
+
+impl<'a> Decoder<'a> for GovernanceCall<'a> {
+    fn decode(source: &mut Source<'a>) -> Result<Self, Error> {
+        let version = source.read_byte()?; //version
+        if version != 0 {
+            return Err(Error::IrregularData);
+        }
+        let method: &str = source.read()?;
+        let params: &[u8] = source.read()?;
+        let mut source = Source::new(params);
+        let call = match method {
+            "authorizeForPeer" | "authorizeForPeerTransferFrom" => {
+                let (addr, peers, amounts) = read_peers(&mut source)?;
+                let transfer_from = method == "authorizeForPeerTransferFrom";
+                GovernanceCall::Authorize { addr, peers, amounts, transfer_from }
+            }
+            "unAuthorizeForPeer" => {
+                let (addr, peers, amounts) = read_peers(&mut source)?;
+                GovernanceCall::UnAuthorize { addr, peers, amounts }
+            }
+            "withdraw" => {
+                let (addr, peers, amounts) = read_peers(&mut source)?;
+                GovernanceCall::Withdraw { addr, peers, amounts }
+            }
+            "withdrawOng" => GovernanceCall::WithdrawOng { addr: source.read_native_address()? },
+            _ => return Err(Error::IrregularData),
+        };
+        Ok(call)
+    }
+}
+
+pub(crate) fn authorize_key(peer: &str, addr: &Address) -> Vec<u8> {
+    [b"auth".as_ref(), peer.as_bytes(), addr.as_bytes()].concat()
+}
+
+pub(crate) fn unbound_ong_key(addr: &Address) -> Vec<u8> {
+    [b"ong".as_ref(), addr.as_bytes()].concat()
+}
+
+//the ONT authorized to the peer, and the ONT ready to withdraw.
+fn authorization(peer: &str, addr: &Address) -> (U128, U128) {
+    database::get(authorize_key(peer, addr)).unwrap_or_default()
+}
+
+///Entry of the simulated governance native contract, to be deployed on `MockChain` along with the
+///ONT and ONG ones.
+///
+///Authorized ONT is deposited in the governance contract and tracked per peer and address. Unlike
+///the chain, the ONT unauthorized is ready to withdraw at once instead of the next consensus round,
+///and the unbound ONG paid by `withdrawOng` is set up by `MockChain::set_unbound_ong`.
+pub fn native_governance_invoke() {
+    let input = runtime::input();
+    let call: GovernanceCall =
+        Source::new(&input).read().unwrap_or_else(|_| runtime::panic("invalid input"));
+    match call {
+        GovernanceCall::Authorize { addr, peers, amounts, transfer_from } => {
+            native_check_witness(addr);
+            let total = U128::new(amounts.iter().map(|&pos| pos as u128).sum());
+            let deposited = if transfer_from {
+                ont::transfer_from(&GOV_CONTRACT_ADDRESS, addr, &GOV_CONTRACT_ADDRESS, total)
+            } else {
+                ont::transfer(addr, &GOV_CONTRACT_ADDRESS, total)
+            };
+            if !deposited {
+                runtime::panic("deposit failed")
+            }
+            for (peer, &pos) in peers.iter().zip(amounts.iter()) {
+                let (authorized, withdrawable) = authorization(peer, addr);
+                let info = (authorized + U128::new(pos as u128), withdrawable);
+                database::put(authorize_key(peer, addr), info);
+            }
+        }
+        GovernanceCall::UnAuthorize { addr, peers, amounts } => {
+            native_check_witness(addr);
+            for (peer, &pos) in peers.iter().zip(amounts.iter()) {
+                let (authorized, withdrawable) = authorization(peer, addr);
+                let pos = U128::new(pos as u128);
+                if authorized < pos {
+                    runtime::panic("authorization insufficient")
+                }
+                let info = (authorized - pos, withdrawable + pos);
+                database::put(authorize_key(peer, addr), info);
+            }
+        }
+        GovernanceCall::Withdraw { addr, peers, amounts } => {
+            native_check_witness(addr);
+            for (peer, &amount) in peers.iter().zip(amounts.iter()) {
+                let (authorized, withdrawable) = authorization(peer, addr);
+                let amount = U128::new(amount as u128);
+                if withdrawable < amount {
+                    runtime::panic("withdrawable insufficient")
+                }
+                let info = (authorized, withdrawable - amount);
+                database::put(authorize_key(peer, addr), info);
+            }
+            let total = U128::new(amounts.iter().map(|&amount| amount as u128).sum());
+            if !ont::transfer(&GOV_CONTRACT_ADDRESS, addr, total) {
+                runtime::panic("withdraw failed")
+            }
+        }
+        GovernanceCall::WithdrawOng { addr } => {
+            native_check_witness(addr);
+            let unbound: U128 = database::get(unbound_ong_key(addr)).unwrap_or_default();
+            database::delete(unbound_ong_key(addr));
+            if !unbound.is_zero() && !ong::v2::transfer(&GOV_CONTRACT_ADDRESS, addr, unbound) {
+                runtime::panic("withdraw failed")
+            }
+        }
+    }
+    runtime::ret(&[1])
 }
 
 pub enum NeoCommand<'a> {
@@ -84,3 +399,191 @@ impl<'a> Decoder<'a> for NeoCommand<'a> {
         }
     }
 }
+
+#[cfg(test)]
+fn native_user_contract() {
+    use crate::contract::governance;
+    let input = runtime::input();
+    let mut source = Source::new(&input);
+    let res = match source.read().unwrap() {
+        "transfer" => {
+            let (from, to, amount) = source.read().unwrap();
+            ont::transfer(from, to, amount)
+        }
+        "transfer_v2" => {
+            let (from, to, amount) = source.read().unwrap();
+            ong::v2::transfer(from, to, amount)
+        }
+        "transfer_multi" => {
+            let (to, amount): (&Address, U128) = source.read().unwrap();
+            let self_addr = runtime::address();
+            let states = [
+                TransferParam { from: self_addr, to: *to, amount },
+                TransferParam { from: self_addr, to: *to, amount },
+            ];
+            ont::transfer_multi(&states)
+        }
+        "approve" => {
+            let (to, amount) = source.read().unwrap();
+            ont::approve(&runtime::address(), to, amount)
+        }
+        "transfer_from" => {
+            let (from, to, amount) = source.read().unwrap();
+            ont::transfer_from(&runtime::address(), from, to, amount)
+        }
+        "authorize" => {
+            let (peer, amount): (&str, U128) = source.read().unwrap();
+            governance::authorize_for_peer(&runtime::address(), amount, peer)
+        }
+        "authorize_from" => {
+            let (peer, amount): (&str, U128) = source.read().unwrap();
+            governance::authorize_for_peer_transfer_from(&runtime::address(), amount, peer)
+        }
+        "unauthorize" => {
+            let (peer, amount): (&str, U128) = source.read().unwrap();
+            governance::un_authorize_for_peer(&runtime::address(), amount, peer)
+        }
+        "withdraw" => {
+            let (peer, amount): (&str, U128) = source.read().unwrap();
+            governance::withdraw(&runtime::address(), amount, peer)
+        }
+        "withdraw_ong" => governance::withdraw_ong(&runtime::address()),
+        "query" => {
+            let (owner, spender) = source.read().unwrap();
+            let mut sink = crate::abi::Sink::new(48);
+            sink.write((ont::balance_of(owner), ong::v2::balance_of(owner)));
+            sink.write(ont::allowance(owner, spender));
+            runtime::ret(sink.bytes())
+        }
+        _ => false,
+    };
+    runtime::ret(&[res as u8])
+}
+
+#[test]
+fn test_native_asset() {
+    use super::MockChain;
+    use crate::abi::Sink;
+    use crate::contract::{ong, ont};
+    let (ont_addr, ong_addr) = (ont::ONT_CONTRACT_ADDRESS, ong::ONG_CONTRACT_ADDRESS);
+    let (a, b, user) = (Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3));
+    let chain = MockChain::new();
+    chain.deploy_native().deploy(&a, native_user_contract).deploy(&b, native_user_contract);
+    chain.set_native_balance(&ont_addr, &a, U128::new(10 * NATIVE_V2_SCALE));
+    chain.set_native_balance(&ong_addr, &a, U128::new(10));
+
+    // the calling contract is a witness of itself.
+    let mut sink = Sink::new(64);
+    sink.write(("transfer", &a, &user, U128::new(3)));
+    assert_eq!(chain.invoke(&a, sink.bytes()), Ok(vec![1]));
+    assert_eq!(chain.native_balance(&ont_addr, &user), U128::new(3 * NATIVE_V2_SCALE));
+    let mut sink = Sink::new(64);
+    sink.write(("transfer_v2", &a, &user, U128::new(4)));
+    assert_eq!(chain.invoke(&a, sink.bytes()), Ok(vec![1]));
+    assert_eq!(chain.native_balance(&ong_addr, &user), U128::new(4));
+    assert_eq!(chain.native_balance(&ong_addr, &a), U128::new(6));
+    assert_eq!(chain.runtime().inner.borrow().notify.len(), 2);
+
+    // neither signed by the user nor called by it.
+    let mut sink = Sink::new(64);
+    sink.write(("transfer", &user, &a, U128::new(1)));
+    assert!(chain.invoke(&a, sink.bytes()).is_err());
+    chain.runtime().witness([user]);
+    assert_eq!(chain.invoke(&a, sink.bytes()), Ok(vec![1]));
+    chain.runtime().witness(Vec::<Address>::new());
+
+    let mut sink = Sink::new(64);
+    sink.write(("transfer", &a, &user, U128::new(100)));
+    assert!(chain.invoke(&a, sink.bytes()).is_err());
+
+    let mut sink = Sink::new(64);
+    sink.write(("transfer_multi", &user, U128::new(2)));
+    assert_eq!(chain.invoke(&a, sink.bytes()), Ok(vec![1]));
+    assert_eq!(chain.native_balance(&ont_addr, &a), U128::new(4 * NATIVE_V2_SCALE));
+
+    let mut sink = Sink::new(64);
+    sink.write(("approve", &b, U128::new(3)));
+    assert_eq!(chain.invoke(&a, sink.bytes()), Ok(vec![1]));
+    let mut sink = Sink::new(64);
+    sink.write(("transfer_from", &a, &user, U128::new(2)));
+    assert_eq!(chain.invoke(&b, sink.bytes()), Ok(vec![1]));
+    assert!(chain.invoke(&b, sink.bytes()).is_err());
+
+    let mut sink = Sink::new(64);
+    sink.write(("query", &a, &b));
+    let output = chain.invoke(&a, sink.bytes()).unwrap();
+    let res: (U128, U128, U128) = Source::new(&output).read().unwrap();
+    assert_eq!(res, (U128::new(2), U128::new(6), U128::new(1)));
+
+    // malformed payloads are all rejected as invalid input.
+    let mut params = Sink::new(64);
+    params.write_native_varuint(1);
+    params.write_byte(19);
+    params.write(a);
+    for (version, params) in [(1, &[1, 20][..]), (0, &[0][..]), (0, params.bytes())] {
+        let mut sink = Sink::new(64);
+        sink.write(version as u8);
+        sink.write("transfer");
+        sink.write(params);
+        assert_eq!(chain.invoke(&ont_addr, sink.bytes()), Err("invalid input".to_string()));
+    }
+}
+
+#[test]
+fn test_native_governance() {
+    use super::MockChain;
+    use crate::abi::Sink;
+    use crate::contract::governance::AuthorizeForPeerParam;
+    let (ont_addr, ong_addr) = (ont::ONT_CONTRACT_ADDRESS, ong::ONG_CONTRACT_ADDRESS);
+    let gov = GOV_CONTRACT_ADDRESS;
+    let (a, user) = (Address::repeat_byte(1), Address::repeat_byte(3));
+    let chain = MockChain::new();
+    chain.deploy_native().deploy(&a, native_user_contract);
+    chain.set_native_balance(&ont_addr, &a, U128::new(10 * NATIVE_V2_SCALE));
+    let call = |method: &str, amount: u128| {
+        let mut sink = Sink::new(64);
+        sink.write((method, "peer", U128::new(amount)));
+        chain.invoke(&a, sink.bytes())
+    };
+    let ont_balance =
+        |addr: &Address| chain.native_balance(&ont_addr, addr).raw() / NATIVE_V2_SCALE;
+
+    assert_eq!(call("authorize", 6), Ok(vec![1]));
+    assert_eq!((ont_balance(&a), ont_balance(&gov)), (4, 6));
+    assert_eq!(chain.authorization("peer", &a), (U128::new(6), U128::new(0)));
+    assert!(call("authorize", 5).is_err());
+
+    assert!(call("unauthorize", 7).is_err());
+    assert_eq!(call("unauthorize", 4), Ok(vec![1]));
+    assert_eq!(chain.authorization("peer", &a), (U128::new(2), U128::new(4)));
+    assert!(call("withdraw", 5).is_err());
+    assert_eq!(call("withdraw", 3), Ok(vec![1]));
+    assert_eq!((ont_balance(&a), ont_balance(&gov)), (7, 3));
+    assert_eq!(chain.authorization("peer", &a), (U128::new(2), U128::new(1)));
+
+    // the deposit taken by transferFrom needs an allowance to the governance contract.
+    assert!(call("authorize_from", 2).is_err());
+    let mut sink = Sink::new(64);
+    sink.write(("approve", &gov, U128::new(2)));
+    assert_eq!(chain.invoke(&a, sink.bytes()), Ok(vec![1]));
+    assert_eq!(call("authorize_from", 2), Ok(vec![1]));
+    assert_eq!(ont_balance(&a), 5);
+    assert_eq!(chain.authorization("peer", &a), (U128::new(4), U128::new(1)));
+
+    chain.set_unbound_ong(&a, U128::new(5));
+    assert_eq!(call("withdraw_ong", 0), Ok(vec![1]));
+    assert_eq!(call("withdraw_ong", 0), Ok(vec![1]));
+    assert_eq!(chain.native_balance(&ong_addr, &a), U128::new(5));
+    assert_eq!(chain.native_balance(&ong_addr, &gov), U128::new(0));
+
+    // authorizing for others needs their witness.
+    let mut params = Sink::new(64);
+    params.write(AuthorizeForPeerParam::new(&user, &[1], &["peer"]));
+    let mut sink = Sink::new(64);
+    sink.write((0u8, "authorizeForPeer", params.bytes()));
+    chain.set_native_balance(&ont_addr, &user, U128::new(NATIVE_V2_SCALE));
+    assert!(chain.invoke(&gov, sink.bytes()).is_err());
+    chain.runtime().witness([user]);
+    assert_eq!(chain.invoke(&gov, sink.bytes()), Ok(vec![1]));
+    assert_eq!(chain.authorization("peer", &user), (U128::new(1), U128::new(0)));
+}
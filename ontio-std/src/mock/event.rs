@@ -0,0 +1,174 @@
+use crate::abi::event_builder::{
+    TYPE_ADDRESS, TYPE_BOOL, TYPE_BYTEARRAY, TYPE_H256, TYPE_INT, TYPE_LIST, TYPE_STRING,
+};
+use crate::abi::{Encoder, Error, Sink, Source};
+use crate::types::{Address, H256, U128};
+
+/// Field of a captured event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EventValue {
+    ByteArray(Vec<u8>),
+    String(String),
+    Address(Address),
+    Bool(bool),
+    Int(U128),
+    H256(H256),
+}
+
+impl From<&[u8]> for EventValue {
+    fn from(val: &[u8]) -> Self {
+        EventValue::ByteArray(val.to_vec())
+    }
+}
+
+impl From<&str> for EventValue {
+    fn from(val: &str) -> Self {
+        EventValue::String(val.to_string())
+    }
+}
+
+impl From<Address> for EventValue {
+    fn from(val: Address) -> Self {
+        EventValue::Address(val)
+    }
+}
+
+impl From<&Address> for EventValue {
+    fn from(val: &Address) -> Self {
+        EventValue::Address(*val)
+    }
+}
+
+impl From<bool> for EventValue {
+    fn from(val: bool) -> Self {
+        EventValue::Bool(val)
+    }
+}
+
+impl From<U128> for EventValue {
+    fn from(val: U128) -> Self {
+        EventValue::Int(val)
+    }
+}
+
+impl From<H256> for EventValue {
+    fn from(val: H256) -> Self {
+        EventValue::H256(val)
+    }
+}
+
+impl Encoder for EventValue {
+    fn encode(&self, sink: &mut Sink) {
+        match self {
+            EventValue::ByteArray(val) => sink.write(val.as_slice()),
+            EventValue::String(val) => sink.write(val.as_str()),
+            EventValue::Address(val) => sink.write(val),
+            EventValue::Bool(val) => sink.write(*val),
+            EventValue::Int(val) => sink.write(*val),
+            EventValue::H256(val) => sink.write(val),
+        }
+    }
+}
+
+/// Event captured by the mock runtime.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// Event built by `EventBuilder` or the `#[event]` macro, the first value is the name.
+    VmValue(Vec<EventValue>),
+    /// Event encoded by `Sink`, such as the events of `#[contract]`, which only carry the fields.
+    Encoded(Vec<u8>),
+}
+
+impl Event {
+    pub fn decode(data: &[u8]) -> Event {
+        match decode_vm_value(data) {
+            Ok(values) => Event::VmValue(values),
+            Err(_) => Event::Encoded(data.to_vec()),
+        }
+    }
+
+    /// Name of the event, the encoded events have none.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Event::VmValue(values) => match values.first() {
+                Some(EventValue::String(name)) => Some(name),
+                _ => None,
+            },
+            Event::Encoded(_) => None,
+        }
+    }
+
+    /// Check the event has `name` and the values of `fields`. The encoded events are compared
+    /// with the `Sink` encoding of `fields` only, as their name is not notified.
+    pub fn matches(&self, name: &str, fields: &[EventValue]) -> bool {
+        match self {
+            Event::VmValue(values) => {
+                values.first() == Some(&EventValue::String(name.to_string()))
+                    && &values[1..] == fields
+            }
+            Event::Encoded(data) => {
+                let mut sink = Sink::new(64);
+                fields.iter().for_each(|field| sink.write(field));
+                sink.bytes() == data.as_slice()
+            }
+        }
+    }
+}
+
+fn decode_vm_value(data: &[u8]) -> Result<Vec<EventValue>, Error> {
+    let mut source = Source::new(data);
+    if source.next_bytes(4)? != b"evt\0" || source.read_byte()? != TYPE_LIST {
+        return Err(Error::TypeInconsistency);
+    }
+    let len = source.read_u32()?;
+    let mut values = Vec::new();
+    for _ in 0..len {
+        let value = match source.read_byte()? {
+            TYPE_BYTEARRAY => {
+                let len = source.read_u32()?;
+                EventValue::ByteArray(source.next_bytes(len as usize)?.to_vec())
+            }
+            TYPE_STRING => {
+                let len = source.read_u32()?;
+                let buf = source.next_bytes(len as usize)?;
+                let val = std::str::from_utf8(buf).map_err(|_| Error::InvalidUtf8)?;
+                EventValue::String(val.to_string())
+            }
+            TYPE_ADDRESS => EventValue::Address(*source.read_address()?),
+            TYPE_BOOL => EventValue::Bool(source.read_bool()?),
+            TYPE_INT => EventValue::Int(source.read_u128()?),
+            TYPE_H256 => EventValue::H256(*source.read_h256()?),
+            _ => return Err(Error::TypeInconsistency),
+        };
+        values.push(value);
+    }
+    if source.skip(1).is_ok() {
+        return Err(Error::LengthInconsistency);
+    }
+    Ok(values)
+}
+
+#[test]
+fn test_decode() {
+    use crate::abi::EventBuilder;
+    use crate::mock::build_runtime;
+    let handle = build_runtime();
+    let (from, amount) = (Address::repeat_byte(1), U128::new(100));
+    EventBuilder::new().string("Transfer").address(&from).number(amount).bool(true).notify();
+    let mut sink = Sink::new(64);
+    sink.write((&from, amount));
+    crate::runtime::notify(sink.bytes());
+    crate::runtime::notify(b"raw");
+
+    let events = handle.events();
+    assert_eq!(
+        events[0],
+        Event::VmValue(vec!["Transfer".into(), from.into(), amount.into(), true.into()])
+    );
+    assert_eq!(events[1].name(), None);
+    assert_eq!(events[1], Event::Encoded(sink.bytes().to_vec()));
+    assert!(events[1].matches("Transfer", &[from.into(), amount.into()]));
+    assert!(!events[1].matches("Transfer", &[from.into()]));
+    assert_eq!(events[2], Event::Encoded(b"raw".to_vec()));
+    handle.assert_emitted("Transfer", &[from.into(), amount.into(), true.into()]);
+}
@@ -0,0 +1,93 @@
+/// Gas charged by the mock runtime for the host calls.
+///
+/// The default schedule approximates the fees of the ontology chain, tests tracking gas
+/// regressions can tune it with `RuntimeHandle::gas_schedule`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GasSchedule {
+    /// Charged for each storage read.
+    pub storage_read: u64,
+    /// Charged for each byte of the value read from storage.
+    pub storage_read_byte: u64,
+    /// Charged for each storage write.
+    pub storage_write: u64,
+    /// Charged for each byte of the key and value written to storage.
+    pub storage_write_byte: u64,
+    /// Charged for each storage delete.
+    pub storage_delete: u64,
+    /// Charged for each notification.
+    pub notify: u64,
+    /// Charged for each contract call, the gas used by the callee is charged separately.
+    pub call_contract: u64,
+    /// Charged for each sha256 hash.
+    pub sha256: u64,
+    /// Charged for each keccak256 hash computed by the host, with the `host-crypto` feature.
+    pub keccak256: u64,
+    /// Charged for each ripemd160 hash computed by the host, with the `host-crypto` feature.
+    pub ripemd160: u64,
+    /// Charged for each signature verified by the host, with the `host-crypto` feature.
+    pub verify_signature: u64,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        GasSchedule {
+            storage_read: 200,
+            storage_read_byte: 0,
+            storage_write: 0,
+            storage_write_byte: 4,
+            storage_delete: 100,
+            notify: 1000,
+            call_contract: 10,
+            sha256: 10,
+            keccak256: 10,
+            ripemd160: 10,
+            verify_signature: 1000,
+        }
+    }
+}
+
+/// Unwinding payload raised when the gas limit is exceeded.
+///
+/// Unlike the other failures, running out of gas is not caught by the nested calls: it aborts the
+/// whole invocation as on chain.
+pub(crate) struct OutOfGas;
+
+/// Gas accounting of the current invocation.
+#[derive(Default)]
+pub(crate) struct GasMeter {
+    pub(crate) schedule: GasSchedule,
+    pub(crate) limit: Option<u64>,
+    pub(crate) price: u64,
+    pub(crate) used: u64,
+}
+
+impl GasMeter {
+    /// Charge `gas`, aborting the execution when the limit is exceeded.
+    pub(crate) fn charge(&mut self, gas: u64) {
+        let used = self.used.saturating_add(gas);
+        match self.limit {
+            Some(limit) if used > limit => {
+                self.used = limit;
+                std::panic::resume_unwind(Box::new(OutOfGas));
+            }
+            _ => self.used = used,
+        }
+    }
+
+    pub(crate) fn left(&self) -> u64 {
+        match self.limit {
+            Some(limit) => limit - self.used,
+            None => u64::MAX,
+        }
+    }
+}
+
+#[test]
+fn test_charge() {
+    let mut meter = GasMeter { limit: Some(100), ..GasMeter::default() };
+    meter.charge(60);
+    assert_eq!(meter.left(), 40);
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| meter.charge(41)));
+    assert!(res.unwrap_err().is::<OutOfGas>());
+    assert_eq!((meter.used, meter.left()), (100, 0));
+}
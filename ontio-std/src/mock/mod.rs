@@ -1,9 +1,18 @@
 pub mod contract_mock;
-pub use contract_mock::{Command, NeoCommand};
+pub use contract_mock::{
+    native_asset_invoke, native_governance_invoke, Command, GovernanceCall, NativeCall, NeoCommand,
+    NATIVE_V2_SCALE,
+};
+mod chain;
+pub use self::chain::MockChain;
+mod event;
+pub use self::event::{Event, EventValue};
+mod gas;
+pub use self::gas::GasSchedule;
 mod runtime;
 use self::runtime::setup_runtime;
-pub use self::runtime::Runtime;
-use self::runtime::RuntimeInner;
+use self::runtime::{execute, RuntimeInner};
+pub use self::runtime::{Runtime, Snapshot};
 use crate::abi::{Encoder, Sink};
 use crate::types::{Address, H256};
 use std::cell::RefCell;
@@ -75,7 +84,105 @@ impl RuntimeHandle {
     }
 
     pub fn on_contract_call(
-        &self, func: impl FnMut(&Address, &[u8]) -> Vec<u8> + 'static,
+        &self, mut func: impl FnMut(&Address, &[u8]) -> Vec<u8> + 'static,
+    ) -> &Self {
+        self.inner.borrow_mut().call_contract =
+            Some(Box::new(move |addr, data| Ok(func(addr, data))));
+        self
+    }
+
+    pub fn input<T: AsRef<[u8]>>(&self, data: T) -> &Self {
+        self.inner.borrow_mut().input = data.as_ref().to_vec();
+        self
+    }
+
+    pub fn gas_schedule(&self, schedule: GasSchedule) -> &Self {
+        self.inner.borrow_mut().gas.schedule = schedule;
+        self
+    }
+
+    /// Abort the invocations using more than `limit` gas with the message `out of gas`.
+    ///
+    /// Running out of gas in a nested call aborts the whole invocation, `try_call_contract` does
+    /// not return it as a failure of the callee.
+    pub fn gas_limit(&self, limit: u64) -> &Self {
+        self.inner.borrow_mut().gas.limit = Some(limit);
+        self
+    }
+
+    pub fn gas_price(&self, price: u64) -> &Self {
+        self.inner.borrow_mut().gas.price = price;
+        self
+    }
+
+    /// Gas used by the last invocation, including the nested contract calls.
+    ///
+    /// The host calls made directly by the test outside of `invoke` are accumulated as well.
+    pub fn gas_used(&self) -> u64 {
+        self.inner.borrow().gas.used
+    }
+
+    /// Events notified so far, in order.
+    pub fn events(&self) -> Vec<Event> {
+        self.inner.borrow().notify.iter().map(|data| Event::decode(data)).collect()
+    }
+
+    /// Assert an event with `name` and `fields` has been notified.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use ontio_std::mock::build_runtime;
+    /// # use ontio_std::types::{Address, U128};
+    /// # let (from, to) = (Address::repeat_byte(1), Address::repeat_byte(2));
+    /// let handle = build_runtime();
+    /// handle.assert_emitted("Transfer", &[from.into(), to.into(), U128::new(100).into()]);
+    /// ```
+    #[track_caller]
+    pub fn assert_emitted(&self, name: &str, fields: &[EventValue]) -> &Self {
+        let events = self.events();
+        if !events.iter().any(|event| event.matches(name, fields)) {
+            panic!("event {} {:?} not emitted, events: {:?}", name, fields, events);
+        }
+        self
+    }
+
+    /// Capture the storage and the notifications, to be restored later by `revert`.
+    pub fn snapshot(&self) -> Snapshot {
+        self.inner.borrow().snapshot()
+    }
+
+    /// Restore the storage and the notifications captured by `snapshot`.
+    pub fn revert(&self, snapshot: &Snapshot) -> &Self {
+        self.inner.borrow_mut().revert(snapshot.clone());
+        self
+    }
+
+    /// Execute the contract entry `invoke` with `input` as the transaction payload.
+    ///
+    /// Returns the bytes passed to `runtime::ret`, or the panic message if the contract aborts.
+    /// Like on the chain, an aborted execution leaves no writes to the storage and no notifications.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use ontio_std::mock::build_runtime;
+    /// # use ontio_std::abi::Sink;
+    /// # fn invoke() {}
+    /// let mut sink = Sink::new(16);
+    /// sink.write("name");
+    /// let output = build_runtime().invoke(invoke, sink.bytes()).unwrap();
+    /// ```
+    pub fn invoke(&self, invoke: impl FnOnce(), input: &[u8]) -> Result<Vec<u8>, String> {
+        {
+            let mut inner = self.inner.borrow_mut();
+            inner.input = input.to_vec();
+            inner.gas.used = 0;
+        }
+        execute(&self.inner, invoke)
+    }
+
+    /// Same as `on_contract_call`, returning `Err` simulates a failed callee with the given output.
+    pub fn on_contract_call_result(
+        &self, func: impl FnMut(&Address, &[u8]) -> Result<Vec<u8>, Vec<u8>> + 'static,
     ) -> &Self {
         self.inner.borrow_mut().call_contract = Some(Box::new(func));
         self
@@ -97,3 +204,104 @@ fn test_call_contract() {
     build_runtime().on_contract_call(|_addr, _data| -> Vec<u8> { vec![1, 2, 3] });
     assert_eq!(crate::runtime::call_contract(&Address::repeat_byte(1), &[1, 2]), vec![1, 2, 3]);
 }
+
+#[test]
+fn test_try_call_contract() {
+    use crate::runtime::{try_call_contract, CallError};
+    build_runtime().on_contract_call_result(|addr, _data| {
+        if addr == &Address::repeat_byte(1) {
+            Ok(vec![1])
+        } else {
+            Err(b"reverted".to_vec())
+        }
+    });
+    assert_eq!(try_call_contract(&Address::repeat_byte(1), &[]), Ok(vec![1]));
+    assert_eq!(
+        try_call_contract(&Address::repeat_byte(2), &[]),
+        Err(CallError { output: b"reverted".to_vec() })
+    );
+}
+
+#[test]
+#[should_panic(expected = "call contract failed")]
+fn test_call_contract_failed() {
+    build_runtime().on_contract_call_result(|_addr, _data| Err(Vec::new()));
+    crate::runtime::call_contract(&Address::repeat_byte(1), &[]);
+}
+
+#[test]
+fn test_snapshot() {
+    let handle = build_runtime();
+    handle.storage_put_raw(b"a", b"1");
+    let snapshot = handle.snapshot();
+    handle.storage_put_raw(b"a", b"2").storage_put_raw(b"b", b"3");
+    crate::runtime::notify(b"event");
+    handle.revert(&snapshot);
+    assert_eq!(handle.storage_read(b"a"), Some(b"1".to_vec()));
+    assert_eq!(handle.storage_read(b"b"), None);
+    assert!(handle.inner.borrow().notify.is_empty());
+}
+
+#[test]
+fn test_gas() {
+    use crate::runtime;
+    fn invoke() {
+        crate::database::put(b"key", 1u64);
+        runtime::notify(b"event");
+        runtime::sha256(b"data");
+    }
+
+    let handle = build_runtime();
+    handle.gas_price(500);
+    let schedule = GasSchedule::default();
+    let expected = schedule.storage_write
+        + schedule.storage_write_byte * 11
+        + schedule.notify
+        + schedule.sha256;
+    assert_eq!(handle.invoke(invoke, &[]), Ok(Vec::new()));
+    assert_eq!(handle.gas_used(), expected);
+    assert_eq!(runtime::gas_info(), (u64::MAX, 500));
+
+    handle.gas_limit(expected - 1);
+    assert_eq!(handle.invoke(invoke, &[]), Err("out of gas".to_string()));
+    assert_eq!(handle.gas_used(), expected - 1);
+    assert_eq!(handle.storage_read(b"key"), Some(1u64.to_le_bytes().to_vec()));
+
+    handle.gas_schedule(GasSchedule { notify: 0, ..schedule.clone() });
+    assert_eq!(handle.invoke(invoke, &[]), Ok(Vec::new()));
+    assert_eq!(runtime::gas_info().0, schedule.notify - 1);
+}
+
+#[test]
+fn test_invoke() {
+    use crate::abi::Source;
+    use crate::runtime;
+    fn invoke() {
+        let input = runtime::input();
+        let mut source = Source::new(&input);
+        match source.read().unwrap() {
+            "echo" => runtime::ret(source.read::<&[u8]>().unwrap()),
+            "abort" => {
+                crate::database::put(b"key", b"value".as_ref());
+                runtime::notify(b"event");
+                runtime::panic("aborted")
+            }
+            _ => {}
+        }
+    }
+
+    let handle = build_runtime();
+    let mut sink = Sink::new(16);
+    sink.write(("echo", b"hello".as_ref()));
+    assert_eq!(handle.invoke(invoke, sink.bytes()), Ok(b"hello".to_vec()));
+
+    let mut sink = Sink::new(16);
+    sink.write("abort");
+    assert_eq!(handle.invoke(invoke, sink.bytes()), Err("aborted".to_string()));
+    assert_eq!(handle.storage_read(b"key"), None);
+    assert!(handle.inner.borrow().notify.is_empty());
+
+    let mut sink = Sink::new(16);
+    sink.write("none");
+    assert_eq!(handle.invoke(invoke, sink.bytes()), Ok(Vec::new()));
+}
@@ -1,10 +1,22 @@
+use super::gas::{GasMeter, OutOfGas};
+use crate::abi::Sink;
+use crate::crypto::KeyType;
+use crate::runtime;
 use crate::types::{Address, H256};
+use std::any::Any;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
 
 use sha2::Digest;
 
+pub(crate) type CallContractFn = dyn FnMut(&Address, &[u8]) -> Result<Vec<u8>, Vec<u8>>;
+pub(crate) type ContractEntry = dyn Fn();
+pub(crate) type Storage = BTreeMap<Vec<u8>, Vec<u8>>;
+
 /// Mock of contract execution runtime
 #[derive(Default)]
 pub struct Runtime {
@@ -13,7 +25,7 @@ pub struct Runtime {
 
 #[derive(Default)]
 pub(crate) struct RuntimeInner {
-    pub(crate) storage: HashMap<Vec<u8>, Vec<u8>>,
+    pub(crate) storage: Storage,
     pub(crate) timestamp: u64,
     pub(crate) block_height: u64,
     pub(crate) caller: Address,
@@ -23,29 +35,172 @@ pub(crate) struct RuntimeInner {
     pub(crate) tx_hash: H256,
     pub(crate) witness: Vec<Address>,
     pub(crate) notify: Vec<Vec<u8>>,
-    pub(crate) call_contract: Option<Box<dyn FnMut(&Address, &[u8]) -> Vec<u8>>>,
+    pub(crate) call_contract: Option<Box<CallContractFn>>,
     pub(crate) call_output: Vec<u8>,
+    pub(crate) input: Vec<u8>,
+    pub(crate) output: Option<Vec<u8>>,
+    pub(crate) contracts: HashMap<Address, Rc<ContractEntry>>,
+    /// Storage of the contracts other than the executing one, which lives in `storage`.
+    pub(crate) storages: HashMap<Address, Storage>,
+    pub(crate) gas: GasMeter,
+}
+
+/// Unwinding payload used by `ontio_return` to leave the contract execution.
+pub(crate) struct ContractReturn;
+
+/// State of the chain captured by `RuntimeHandle::snapshot`: the storage of all the contracts and
+/// the notifications.
+#[derive(Clone)]
+pub struct Snapshot {
+    storages: HashMap<Address, Storage>,
+    notify: Vec<Vec<u8>>,
+}
+
+/// Execution context of the calling contract, saved for the duration of a nested execution.
+pub(crate) struct Frame {
+    self_addr: Address,
+    caller: Address,
+    input: Vec<u8>,
+    output: Option<Vec<u8>>,
 }
 
 impl RuntimeInner {
-    fn call_contract(&mut self, addr: &Address, data: &[u8]) -> u32 {
-        let call = self.call_contract.as_mut().expect("call contract callback is not set");
-        self.call_output = (call)(addr, data);
-        self.call_output.len() as u32
+    /// Switch the execution to the contract at `addr`, loading its storage.
+    pub(crate) fn enter(&mut self, addr: &Address, caller: &Address, input: &[u8]) -> Frame {
+        let storage = mem::take(&mut self.storage);
+        self.storages.insert(self.self_addr, storage);
+        self.storage = self.storages.remove(addr).unwrap_or_default();
+        Frame {
+            self_addr: mem::replace(&mut self.self_addr, *addr),
+            caller: mem::replace(&mut self.caller, *caller),
+            input: mem::replace(&mut self.input, input.to_vec()),
+            output: self.output.take(),
+        }
+    }
+
+    /// Switch the execution back to the context saved by `enter`.
+    pub(crate) fn leave(&mut self, frame: Frame) {
+        let storage = mem::take(&mut self.storage);
+        self.storages.insert(self.self_addr, storage);
+        self.storage = self.storages.remove(&frame.self_addr).unwrap_or_default();
+        self.self_addr = frame.self_addr;
+        self.caller = frame.caller;
+        self.input = frame.input;
+        self.output = frame.output;
+    }
+
+    pub(crate) fn snapshot(&self) -> Snapshot {
+        let mut storages = self.storages.clone();
+        storages.insert(self.self_addr, self.storage.clone());
+        Snapshot { storages, notify: self.notify.clone() }
+    }
+
+    pub(crate) fn revert(&mut self, snapshot: Snapshot) {
+        let Snapshot { mut storages, notify } = snapshot;
+        self.storage = storages.remove(&self.self_addr).unwrap_or_default();
+        self.storages = storages;
+        self.notify = notify;
+    }
+
+    pub(crate) fn storage_of(&mut self, addr: &Address) -> &mut Storage {
+        if addr == &self.self_addr {
+            &mut self.storage
+        } else {
+            self.storages.entry(*addr).or_default()
+        }
+    }
+}
+
+/// Run `invoke` as a contract execution, the input must already be set.
+///
+/// Returns the bytes passed to `runtime::ret`, or the panic message if the contract aborts, in
+/// which case all the writes to the chain made by the execution are discarded.
+pub(crate) fn execute(
+    inner: &RefCell<RuntimeInner>, invoke: impl FnOnce(),
+) -> Result<Vec<u8>, String> {
+    execute_nested(inner, invoke).map_err(panic_message)
+}
+
+/// Same as `execute`, returning the unwinding payload of the aborted execution.
+fn execute_nested(
+    inner: &RefCell<RuntimeInner>, invoke: impl FnOnce(),
+) -> Result<Vec<u8>, Box<dyn Any + Send>> {
+    let snapshot = {
+        let mut inner = inner.borrow_mut();
+        inner.output = None;
+        inner.snapshot()
+    };
+    let res = panic::catch_unwind(AssertUnwindSafe(invoke));
+    let mut inner = inner.borrow_mut();
+    let output = inner.output.take();
+    match res {
+        Ok(()) => Ok(output.unwrap_or_default()),
+        Err(err) if err.is::<ContractReturn>() => Ok(output.unwrap_or_default()),
+        Err(err) => {
+            inner.revert(snapshot);
+            Err(err)
+        }
+    }
+}
+
+fn panic_message(err: Box<dyn Any + Send>) -> String {
+    if err.is::<OutOfGas>() {
+        "out of gas".to_string()
+    } else if let Some(msg) = err.downcast_ref::<String>() {
+        msg.clone()
+    } else if let Some(msg) = err.downcast_ref::<&str>() {
+        msg.to_string()
+    } else {
+        String::new()
     }
 }
 
 impl Runtime {
+    fn charge(&self, gas: u64) {
+        self.inner.borrow_mut().gas.charge(gas);
+    }
+
     fn storage_write(&self, key: &[u8], val: &[u8]) {
-        self.inner.borrow_mut().storage.insert(key.into(), val.to_vec());
+        let mut inner = self.inner.borrow_mut();
+        let schedule = &inner.gas.schedule;
+        let gas =
+            schedule.storage_write + schedule.storage_write_byte * (key.len() + val.len()) as u64;
+        inner.gas.charge(gas);
+        inner.storage.insert(key.into(), val.to_vec());
     }
 
     fn storage_read(&self, key: &[u8]) -> Option<Vec<u8>> {
-        self.inner.borrow().storage.get(key).map(|val| val.to_vec())
+        let mut inner = self.inner.borrow_mut();
+        let val = inner.storage.get(key).map(|val| val.to_vec());
+        let schedule = &inner.gas.schedule;
+        let len = val.as_ref().map(|val| val.len()).unwrap_or_default() as u64;
+        let gas = schedule.storage_read + schedule.storage_read_byte * len;
+        inner.gas.charge(gas);
+        val
+    }
+
+    fn storage_scan(&self, prefix: &[u8], start: &[u8], limit: u32) -> Vec<u8> {
+        let mut inner = self.inner.borrow_mut();
+        let start = prefix.max(start).to_vec();
+        let mut sink = Sink::new(64);
+        inner
+            .storage
+            .range(start..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .take(limit as usize)
+            .for_each(|(key, val)| sink.write((key.as_slice(), val.as_slice())));
+        let output = sink.into();
+        let schedule = &inner.gas.schedule;
+        let gas = schedule.storage_read + schedule.storage_read_byte * output.len() as u64;
+        inner.gas.charge(gas);
+        output
     }
 
     fn storage_delete(&self, key: &[u8]) {
-        self.inner.borrow_mut().storage.remove(key);
+        let mut inner = self.inner.borrow_mut();
+        let gas = inner.gas.schedule.storage_delete;
+        inner.gas.charge(gas);
+        inner.storage.remove(key);
     }
 
     fn timestamp(&self) -> u64 {
@@ -81,16 +236,83 @@ impl Runtime {
     }
 
     fn notify(&self, msg: &[u8]) {
-        self.inner.borrow_mut().notify.push(msg.to_vec());
+        let mut inner = self.inner.borrow_mut();
+        let gas = inner.gas.schedule.notify;
+        inner.gas.charge(gas);
+        inner.notify.push(msg.to_vec());
     }
 
     fn sha256(&self, data: &[u8]) -> H256 {
+        let gas = self.inner.borrow().gas.schedule.sha256;
+        self.charge(gas);
         let hash = sha2::Sha256::new().chain(data).finalize();
         H256::from_slice(hash.as_slice())
     }
 
+    fn keccak256(&self, data: &[u8]) -> H256 {
+        let gas = self.inner.borrow().gas.schedule.keccak256;
+        self.charge(gas);
+        crate::crypto::soft::keccak256(data)
+    }
+
+    fn ripemd160(&self, data: &[u8]) -> [u8; 20] {
+        let gas = self.inner.borrow().gas.schedule.ripemd160;
+        self.charge(gas);
+        crate::crypto::soft::ripemd160(data)
+    }
+
+    fn verify_signature(&self, key_type: u32, pubkey: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+        let gas = self.inner.borrow().gas.schedule.verify_signature;
+        self.charge(gas);
+        match u8::try_from(key_type).ok().and_then(KeyType::from_u8) {
+            Some(key_type) => crate::crypto::soft::verify_signature(key_type, pubkey, msg, sig),
+            None => false,
+        }
+    }
+
     fn call_contract(&self, addr: &Address, data: &[u8]) -> u32 {
-        self.inner.borrow_mut().call_contract(addr, data)
+        let gas = self.inner.borrow().gas.schedule.call_contract;
+        self.charge(gas);
+        // the runtime must not stay borrowed while the callee runs, it calls back into the runtime.
+        let contract = self.inner.borrow().contracts.get(addr).cloned();
+        let result = match contract {
+            Some(entry) => {
+                let frame = {
+                    let mut inner = self.inner.borrow_mut();
+                    let caller = inner.self_addr;
+                    inner.enter(addr, &caller, data)
+                };
+                let res = execute_nested(&self.inner, || entry());
+                self.inner.borrow_mut().leave(frame);
+                match res {
+                    // out of gas aborts the caller too, up to the entry of the invocation.
+                    Err(err) if err.is::<OutOfGas>() => panic::resume_unwind(err),
+                    res => res.map_err(|err| panic_message(err).into_bytes()),
+                }
+            }
+            None => {
+                let mut call = self
+                    .inner
+                    .borrow_mut()
+                    .call_contract
+                    .take()
+                    .expect("call contract callback is not set");
+                let res = call(addr, data);
+                self.inner.borrow_mut().call_contract.get_or_insert(call);
+                res
+            }
+        };
+        let mut inner = self.inner.borrow_mut();
+        match result {
+            Ok(output) => {
+                inner.call_output = output;
+                inner.call_output.len() as u32
+            }
+            Err(output) => {
+                inner.call_output = output;
+                runtime::CALL_FAILED
+            }
+        }
     }
 
     fn get_call_output(&self) -> Vec<u8> {
@@ -100,6 +322,19 @@ impl Runtime {
     fn call_output_length(&self) -> u32 {
         self.inner.borrow().call_output.len() as u32
     }
+
+    fn gas_info(&self) -> (u64, u64) {
+        let inner = self.inner.borrow();
+        (inner.gas.left(), inner.gas.price)
+    }
+
+    fn input(&self) -> Vec<u8> {
+        self.inner.borrow().input.clone()
+    }
+
+    fn ret(&self, data: &[u8]) {
+        self.inner.borrow_mut().output = Some(data.to_vec());
+    }
 }
 
 thread_local!(static RUNTIME: RefCell<Runtime> = RefCell::new(Runtime::default()));
@@ -113,20 +348,19 @@ mod env {
     use std::cmp;
     use std::ptr;
     use std::slice;
-    use std::u32;
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_timestamp() -> u64 {
+    pub unsafe extern "C-unwind" fn ontio_timestamp() -> u64 {
         RUNTIME.with(|r| r.borrow().timestamp())
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_block_height() -> u64 {
+    pub unsafe extern "C-unwind" fn ontio_block_height() -> u64 {
         RUNTIME.with(|r| r.borrow().block_height())
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_self_address(dest: *mut u8) {
+    pub unsafe extern "C-unwind" fn ontio_self_address(dest: *mut u8) {
         RUNTIME.with(|r| {
             let addr = r.borrow().address();
             ptr::copy(addr.as_ptr(), dest, Address::len_bytes());
@@ -134,7 +368,14 @@ mod env {
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_caller_address(dest: *mut u8) {
+    pub unsafe extern "C-unwind" fn ontio_gas_info(dest: *mut u8) {
+        let (gas_left, gas_price) = RUNTIME.with(|r| r.borrow().gas_info());
+        ptr::copy(gas_left.to_le_bytes().as_ptr(), dest, 8);
+        ptr::copy(gas_price.to_le_bytes().as_ptr(), dest.add(8), 8);
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C-unwind" fn ontio_caller_address(dest: *mut u8) {
         RUNTIME.with(|r| {
             let caller = r.borrow().caller();
             ptr::copy(caller.as_ptr(), dest, Address::len_bytes());
@@ -142,7 +383,7 @@ mod env {
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_entry_address(dest: *mut u8) {
+    pub unsafe extern "C-unwind" fn ontio_entry_address(dest: *mut u8) {
         RUNTIME.with(|r| {
             let entry = r.borrow().entry_address();
             ptr::copy(entry.as_ptr(), dest, Address::len_bytes());
@@ -150,7 +391,7 @@ mod env {
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_current_blockhash(dest: *mut u8) {
+    pub unsafe extern "C-unwind" fn ontio_current_blockhash(dest: *mut u8) {
         RUNTIME.with(|r| {
             let block_hash = r.borrow().current_blockhash();
             ptr::copy(block_hash.as_ptr(), dest, H256::len_bytes());
@@ -158,7 +399,7 @@ mod env {
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_current_txhash(dest: *mut u8) {
+    pub unsafe extern "C-unwind" fn ontio_current_txhash(dest: *mut u8) {
         RUNTIME.with(|r| {
             let tx_hash = r.borrow().current_txhash();
             ptr::copy(tx_hash.as_ptr(), dest, H256::len_bytes());
@@ -166,20 +407,20 @@ mod env {
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_check_witness(addr: *const u8) -> bool {
+    pub unsafe extern "C-unwind" fn ontio_check_witness(addr: *const u8) -> bool {
         let address = Address::from_slice(slice::from_raw_parts(addr, 20));
         RUNTIME.with(|r| r.borrow().check_witness(&address))
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_storage_read(
+    pub unsafe extern "C-unwind" fn ontio_storage_read(
         key: *const u8, klen: u32, val: *mut u8, vlen: u32, offset: u32,
     ) -> u32 {
         let offset = offset as usize;
         let key = slice::from_raw_parts(key, klen as usize);
         let v = RUNTIME.with(|r| r.borrow().storage_read(key));
         match v {
-            None => u32::MAX,
+            None => runtime::STORAGE_NOT_FOUND,
             Some(v) => {
                 ptr::copy(
                     v.as_slice()[offset..].as_ptr(),
@@ -192,7 +433,21 @@ mod env {
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_storage_write(
+    pub unsafe extern "C-unwind" fn ontio_storage_scan(
+        prefix: *const u8, plen: u32, start: *const u8, slen: u32, limit: u32, val: *mut u8,
+        vlen: u32, offset: u32,
+    ) -> u32 {
+        let offset = offset as usize;
+        let prefix = slice::from_raw_parts(prefix, plen as usize);
+        let start = slice::from_raw_parts(start, slen as usize);
+        let output = RUNTIME.with(|r| r.borrow().storage_scan(prefix, start, limit));
+        let len = cmp::min(vlen as usize, output.len().saturating_sub(offset));
+        ptr::copy(output[cmp::min(offset, output.len())..].as_ptr(), val, len);
+        output.len() as u32
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C-unwind" fn ontio_storage_write(
         key: *const u8, klen: u32, val: *const u8, vlen: u32,
     ) {
         let key = slice::from_raw_parts(key, klen as usize);
@@ -201,19 +456,19 @@ mod env {
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_storage_delete(key: *const u8, klen: u32) {
+    pub unsafe extern "C-unwind" fn ontio_storage_delete(key: *const u8, klen: u32) {
         let key = slice::from_raw_parts(key, klen as usize);
         RUNTIME.with(|r| r.borrow().storage_delete(key));
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_notify(ptr: *const u8, len: u32) {
+    pub unsafe extern "C-unwind" fn ontio_notify(ptr: *const u8, len: u32) {
         let msg = slice::from_raw_parts(ptr, len as usize);
         RUNTIME.with(|r| r.borrow().notify(msg));
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_sha256(ptr: *const u8, len: u32, h256: *mut u8) {
+    pub unsafe extern "C-unwind" fn ontio_sha256(ptr: *const u8, len: u32, h256: *mut u8) {
         let msg = slice::from_raw_parts(ptr, len as usize);
         RUNTIME.with(|r| {
             let hash = r.borrow().sha256(msg);
@@ -222,7 +477,36 @@ mod env {
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_call_contract(
+    pub unsafe extern "C-unwind" fn ontio_keccak256(ptr: *const u8, len: u32, h256: *mut u8) {
+        let msg = slice::from_raw_parts(ptr, len as usize);
+        RUNTIME.with(|r| {
+            let hash = r.borrow().keccak256(msg);
+            ptr::copy(hash.as_ptr(), h256, 32);
+        });
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C-unwind" fn ontio_ripemd160(ptr: *const u8, len: u32, h160: *mut u8) {
+        let msg = slice::from_raw_parts(ptr, len as usize);
+        RUNTIME.with(|r| {
+            let hash = r.borrow().ripemd160(msg);
+            ptr::copy(hash.as_ptr(), h160, 20);
+        });
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C-unwind" fn ontio_verify_signature(
+        key_type: u32, pubkey_ptr: *const u8, pubkey_len: u32, msg_ptr: *const u8, msg_len: u32,
+        sig_ptr: *const u8, sig_len: u32,
+    ) -> u32 {
+        let pubkey = slice::from_raw_parts(pubkey_ptr, pubkey_len as usize);
+        let msg = slice::from_raw_parts(msg_ptr, msg_len as usize);
+        let sig = slice::from_raw_parts(sig_ptr, sig_len as usize);
+        RUNTIME.with(|r| r.borrow().verify_signature(key_type, pubkey, msg, sig) as u32)
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C-unwind" fn ontio_call_contract(
         addr: *const u8, input_ptr: *const u8, input_len: u32,
     ) -> u32 {
         let addr = Address::from_slice(slice::from_raw_parts(addr, 20));
@@ -231,25 +515,24 @@ mod env {
     }
 
     #[no_mangle]
-    pub unsafe extern "C" fn ontio_call_output_length() -> u32 {
+    pub unsafe extern "C-unwind" fn ontio_call_output_length() -> u32 {
         RUNTIME.with(|r| r.borrow().call_output_length())
     }
 
     #[no_mangle]
-    pub fn ontio_panic(_ptr: *const u8, _len: u32) -> ! {
-        panic!("ontio panic");
+    pub unsafe extern "C-unwind" fn ontio_panic(ptr: *const u8, len: u32) -> ! {
+        let msg = slice::from_raw_parts(ptr, len as usize);
+        panic!("{}", String::from_utf8_lossy(msg));
     }
 
     #[no_mangle]
-    pub fn ontio_get_call_output(dst: *mut u8) {
+    pub unsafe extern "C-unwind" fn ontio_get_call_output(dst: *mut u8) {
         let output = RUNTIME.with(|r| r.borrow().get_call_output());
-        unsafe {
-            std::ptr::copy(output.as_ptr(), dst, output.len());
-        }
+        ptr::copy(output.as_ptr(), dst, output.len());
     }
 
     #[no_mangle]
-    pub fn ontio_contract_create(
+    pub unsafe extern "C-unwind" fn ontio_contract_create(
         _code_ptr: *const u8, _code_len: u32, _vm_type: u32, _name_ptr: *const u8, _name_len: u32,
         _ver_ptr: *const u8, _ver_len: u32, _author_ptr: *const u8, _author_len: u32,
         _email_ptr: *const u8, _email_len: u32, _desc_ptr: *const u8, _desc_len: u32,
@@ -259,12 +542,12 @@ mod env {
     }
 
     #[no_mangle]
-    pub fn ontio_contract_destroy() -> ! {
+    pub unsafe extern "C-unwind" fn ontio_contract_destroy() -> ! {
         unimplemented!()
     }
 
     #[no_mangle]
-    pub fn ontio_contract_migrate(
+    pub unsafe extern "C-unwind" fn ontio_contract_migrate(
         _code: *const u8, _code_len: u32, _vm_type: u32, _name_ptr: *const u8, _name_len: u32,
         _ver_ptr: *const u8, _ver_len: u32, _author_ptr: *const u8, _author_len: u32,
         _email_ptr: *const u8, _email_len: u32, _desc_ptr: *const u8, _desc_len: u32,
@@ -274,17 +557,20 @@ mod env {
     }
 
     #[no_mangle]
-    pub fn ontio_input_length() -> u32 {
-        unimplemented!()
+    pub unsafe extern "C-unwind" fn ontio_input_length() -> u32 {
+        RUNTIME.with(|r| r.borrow().input().len() as u32)
     }
 
     #[no_mangle]
-    pub fn ontio_get_input(_dst: *mut u8) {
-        unimplemented!()
+    pub unsafe extern "C-unwind" fn ontio_get_input(dst: *mut u8) {
+        let input = RUNTIME.with(|r| r.borrow().input());
+        ptr::copy(input.as_ptr(), dst, input.len());
     }
 
     #[no_mangle]
-    pub fn ontio_return(_ptr: *const u8, _len: u32) -> ! {
-        unimplemented!()
+    pub unsafe extern "C-unwind" fn ontio_return(ptr: *const u8, len: u32) -> ! {
+        let data = slice::from_raw_parts(ptr, len as usize);
+        RUNTIME.with(|r| r.borrow().ret(data));
+        std::panic::resume_unwind(Box::new(ContractReturn))
     }
 }
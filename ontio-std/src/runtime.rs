@@ -1,7 +1,9 @@
+use crate::abi::Source;
 use crate::prelude::*;
 
 mod env {
-    extern "C" {
+    // `C-unwind` lets the mock runtime abort the execution by unwinding from a host call.
+    extern "C-unwind" {
         pub fn ontio_timestamp() -> u64;
         pub fn ontio_block_height() -> u32;
         pub fn ontio_self_address(dest: *mut u8);
@@ -15,7 +17,6 @@ mod env {
         pub fn ontio_input_length() -> u32;
         pub fn ontio_get_input(dst: *mut u8);
         pub fn ontio_call_contract(addr: *const u8, input_ptr: *const u8, input_len: u32) -> u32;
-        #[allow(unused)]
         pub fn ontio_call_output_length() -> u32;
         pub fn ontio_get_call_output(dst: *mut u8);
         pub fn ontio_current_blockhash(blockhash: *const u8) -> u32;
@@ -31,6 +32,10 @@ mod env {
         ) -> u32;
         pub fn ontio_storage_write(key: *const u8, klen: u32, val: *const u8, vlen: u32);
         pub fn ontio_storage_delete(key: *const u8, klen: u32);
+        pub fn ontio_storage_scan(
+            prefix: *const u8, plen: u32, start: *const u8, slen: u32, limit: u32, val: *mut u8,
+            vlen: u32, offset: u32,
+        ) -> u32;
         pub fn ontio_sha256(data: *const u8, len: u32, val: *mut u8);
         pub fn ontio_contract_create(
             code_ptr: *const u8, code_len: u32, vm_type: u32, name_ptr: *const u8, name_len: u32,
@@ -42,13 +47,25 @@ mod env {
     }
 }
 
-//todo : return result
+/// Status returned by `ontio_storage_read` when the key does not exist.
+pub(crate) const STORAGE_NOT_FOUND: u32 = u32::MAX;
+
+/// Status returned by `ontio_call_contract` when the called contract fails, the output of the
+/// failed call is then read with `ontio_call_output_length` and `ontio_get_call_output`.
+///
+/// The Ontology node aborts the whole execution when a called wasm contract fails and never
+/// returns this status, so on chain `try_call_contract` only returns `Ok`. The mock runtime
+/// reports the failure with this status to let the callers of `try_call_contract` be tested.
+pub(crate) const CALL_FAILED: u32 = u32::MAX;
+
 /// With this method, another contract can be called, When calling Neo contract and native contract across contracts, the parameter construction is slightly different. Please refer to the corresponding examples.
 ///
 /// addr: Called contract address
 ///
 /// input: Parameters required to call the target contract method
 ///
+/// The execution is aborted if the called contract fails, use `try_call_contract` to handle the failure.
+///
 /// # Example
 /// ```no_run
 /// # use ontio_std::abi::Sink;
@@ -60,9 +77,50 @@ mod env {
 /// let res = runtime::call_contract(&addr, sink.bytes());
 /// ```
 pub fn call_contract(addr: &Address, input: &[u8]) -> Vec<u8> {
+    match try_call_contract(addr, input) {
+        Ok(output) => output,
+        Err(_) => panic("call contract failed"),
+    }
+}
+
+/// The error returned by `try_call_contract` when the called contract fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallError {
+    /// The output of the failed call, contains the abort message of the called contract if provided by the chain.
+    pub output: Vec<u8>,
+}
+
+/// Same as `call_contract`, but the failure of the called contract is returned as `Err` instead of aborting.
+///
+/// Only hosts reporting the failure with a status do return `Err`, the Ontology node aborts the
+/// execution instead, see `CALL_FAILED`.
+///
+/// # Example
+/// ```no_run
+/// # use ontio_std::abi::Sink;
+/// # use ontio_std::runtime;
+/// # use ontio_std::types::Address;
+/// let mut sink = Sink::new(16);
+/// let addr = Address::repeat_byte(1u8);
+/// sink.write(("transfer".to_string(), &addr));
+/// match runtime::try_call_contract(&addr, sink.bytes()) {
+///     Ok(output) => runtime::ret(&output),
+///     Err(_) => runtime::ret(&[0]),
+/// }
+/// ```
+pub fn try_call_contract(addr: &Address, input: &[u8]) -> Result<Vec<u8>, CallError> {
     let addr: &[u8] = addr.as_ref();
     let size =
         unsafe { env::ontio_call_contract(addr.as_ptr(), input.as_ptr(), input.len() as u32) };
+    if size == CALL_FAILED {
+        let size = unsafe { env::ontio_call_output_length() };
+        return Err(CallError { output: call_output(size) });
+    }
+
+    Ok(call_output(size))
+}
+
+fn call_output(size: u32) -> Vec<u8> {
     let mut output = vec![0u8; size as usize];
     if size != 0 {
         let value = &mut output[..];
@@ -242,7 +300,7 @@ pub fn storage_read(key: &[u8]) -> Option<Vec<u8>> {
         )
     };
 
-    if size == core::u32::MAX {
+    if size == STORAGE_NOT_FOUND {
         return None;
     }
     let size = size as usize;
@@ -264,6 +322,50 @@ pub fn storage_read(key: &[u8]) -> Option<Vec<u8>> {
     Some(val)
 }
 
+/// Scan the storage for the key/value pairs whose key starts with `prefix`, in ascending key order.
+///
+/// The scan begins at the key `start` (inclusive) and returns at most `limit` pairs, the next page
+/// starts right after the last key returned. The execution aborts if the pairs returned by the
+/// host can not be decoded, rather than returning a truncated page.
+/// # Example
+///
+/// ```no_run
+/// # use ontio_std::runtime;
+/// let pairs = runtime::storage_scan(b"token_", b"", 10);
+/// ```
+///
+pub fn storage_scan(prefix: &[u8], start: &[u8], limit: u32) -> Vec<(Vec<u8>, Vec<u8>)> {
+    const INITIAL: usize = 256;
+    let scan = |buf: &mut [u8], offset: usize| unsafe {
+        env::ontio_storage_scan(
+            prefix.as_ptr(),
+            prefix.len() as u32,
+            start.as_ptr(),
+            start.len() as u32,
+            limit,
+            buf.as_mut_ptr(),
+            buf.len() as u32,
+            offset as u32,
+        )
+    };
+    let mut buf = vec![0; INITIAL];
+    let size = scan(&mut buf, 0) as usize;
+    buf.resize(size, 0);
+    if size > INITIAL {
+        scan(&mut buf[INITIAL..], INITIAL);
+    }
+
+    let mut source = Source::new(&buf);
+    let mut pairs = Vec::new();
+    while source.remaining() > 0 {
+        match source.read::<(&[u8], &[u8])>() {
+            Ok((key, val)) => pairs.push((key.to_vec(), val.to_vec())),
+            Err(_) => panic("invalid storage scan output"),
+        }
+    }
+    pairs
+}
+
 /// Get timestamp in current block
 /// # Example
 ///
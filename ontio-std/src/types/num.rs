@@ -252,6 +252,18 @@ impl Debug for U128 {
     }
 }
 
+impl Display for I128 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Debug for I128 {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
 mod u256 {
     uint::construct_uint! {
         pub struct U256(4);